@@ -0,0 +1,7 @@
+fn main() {
+    // Expose the target triple so the help overlay can report it
+    if let Ok(target) = std::env::var("TARGET") {
+        println!("cargo:rustc-env=RDU_TARGET={}", target);
+    }
+    println!("cargo:rerun-if-changed=build.rs");
+}
@@ -1,65 +1,517 @@
-use crate::{args::Args, file_node::FileNode, scanner::scan_dir, sort::SortMode};
-use ratatui::widgets::ListState;
-use std::{cell::RefCell, path::PathBuf, rc::Rc};
+use crate::{
+    args::Args,
+    clipboard::copy_to_clipboard,
+    colors::Theme,
+    columns::{ColumnKind, default_columns},
+    file_node::{
+        ByteTotals, FileNode, apply_symlink_size, attach, detach, find_path, single_child_chain,
+        update_partial, verify_sizes,
+    },
+    fs_ops::{
+        DeleteMethod, absolute_path, delete_path, log_deletion, move_path, rename_path,
+        restore_from_trash,
+    },
+    scanner::{LiveScan, ScanCache, extension_matches, scan_dir_cached},
+    sort::{DirGrouping, SortMode},
+    tree_output::write_du,
+    utils::{DiskSpace, disk_space, format_size, natural_cmp, rebase_path},
+};
+use ratatui::{
+    layout::{Position, Rect},
+    widgets::ListState,
+};
+use std::{
+    cell::RefCell,
+    cmp::{Ordering, Reverse},
+    collections::{HashMap, HashSet},
+    fs,
+    io::{self, Write},
+    path::{Path, PathBuf},
+    rc::Rc,
+    time::{Duration, SystemTime},
+};
+
+/// A key that waits for a second key press to complete its action
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum PendingKey {
+    SetBookmark,
+    JumpToBookmark,
+}
+
+/// What the text typed at the footer prompt is used for
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum PromptKind {
+    /// New name for the selected entry
+    Rename,
+    /// Destination directory for the marked entries (or the selected one)
+    Move,
+    /// File the `du`-style listing is written to; empty copies it to the clipboard
+    ExportDu,
+}
+
+/// Text being typed at the footer prompt
+#[derive(Debug, Clone)]
+pub struct Prompt {
+    pub kind: PromptKind,
+    pub input: String,
+}
+
+/// Number of entries gathered for the recent files overlay
+const RECENT_FILES_LIMIT: usize = 100;
+
+/// Upper bounds (exclusive) of the size histogram buckets; the last bucket is unbounded
+const HISTOGRAM_BOUNDS: [(&str, u64); 4] = [
+    ("0-1K", 1 << 10),
+    ("1K-1M", 1 << 20),
+    ("1M-100M", 100 << 20),
+    (">100M", u64::MAX),
+];
+
+/// What the percent column is a share of
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum PercentBasis {
+    /// The directory's total in the view's metric
+    #[default]
+    Metric,
+    /// The directory's item count, whatever the view's metric
+    Items,
+    /// The capacity of the disk holding the scanned root, so rows show their footprint on it
+    Disk,
+}
+
+/// A popup drawn on top of the file list
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Overlay {
+    Help,
+    RecentFiles,
+    Histogram,
+    Details,
+    Stats,
+    Peek,
+}
+
+/// A file under the current directory, as listed in the recent files overlay
+#[derive(Debug, Clone)]
+pub struct RecentFile {
+    pub relative_path: PathBuf,
+    pub size: u64,
+    pub modified_time: Option<SystemTime>,
+}
+
+/// Files of one size range below the current directory, as shown in the histogram overlay
+#[derive(Debug, Clone)]
+pub struct HistogramBucket {
+    pub label: &'static str,
+    pub count: u64,
+    pub total_size: u64,
+}
+
+/// Everything known about the selected entry, as shown in the details overlay
+#[derive(Debug, Clone)]
+pub struct NodeDetails {
+    pub path: PathBuf,
+    pub size: u64,
+    pub modified_time: Option<SystemTime>,
+    pub is_dir: bool,
+    pub is_symlink: bool,
+    pub child_count: usize,
+    /// Entries below this one, recursively
+    pub descendant_count: u64,
+}
+
+impl NodeDetails {
+    pub fn from_node(node: &FileNode) -> Self {
+        Self {
+            path: node.path.clone(),
+            size: node.size,
+            modified_time: node.modified_time,
+            is_dir: node.is_dir,
+            is_symlink: fs::symlink_metadata(&node.path).is_ok_and(|m| m.file_type().is_symlink()),
+            child_count: node.child_count(),
+            descendant_count: node.item_count.saturating_sub(1),
+        }
+    }
+}
+
+/// Figures about the whole scanned tree, as shown in the statistics overlay
+#[derive(Debug, Clone, Default)]
+pub struct TreeStats {
+    pub total_size: u64,
+    pub files: u64,
+    /// Bytes in files, which leaves out directory entries counted with `--disk-usage`
+    pub file_bytes: u64,
+    /// Directories below the root
+    pub dirs: u64,
+    /// Levels below the root of the deepest entry, and its path
+    pub max_depth: usize,
+    pub deepest_path: PathBuf,
+    pub largest_file: Option<(PathBuf, u64)>,
+    /// Read errors recorded while scanning
+    pub error_count: usize,
+    /// Of the read errors, those caused by a lack of permission
+    pub denied_count: usize,
+    /// Scan time of each top-level entry, slowest first, with `--scan-timing`
+    pub scan_timings: Vec<(PathBuf, Duration)>,
+}
+
+impl TreeStats {
+    pub fn from_tree(root: &FileNode) -> Self {
+        fn visit(node: &FileNode, depth: usize, stats: &mut TreeStats) {
+            stats.files += node.folded_files;
+            stats.file_bytes += node.folded_size;
+            for child in &node.children {
+                let child = child.borrow();
+                if depth + 1 > stats.max_depth {
+                    stats.max_depth = depth + 1;
+                    stats.deepest_path = child.path.clone();
+                }
+                if child.is_dir {
+                    stats.dirs += 1;
+                    visit(&child, depth + 1, stats);
+                } else {
+                    stats.files += 1;
+                    stats.file_bytes += child.size;
+                    if stats
+                        .largest_file
+                        .as_ref()
+                        .is_none_or(|(_, size)| child.size > *size)
+                    {
+                        stats.largest_file = Some((child.path.clone(), child.size));
+                    }
+                }
+            }
+        }
+
+        let mut stats = TreeStats {
+            total_size: root.size,
+            deepest_path: root.path.clone(),
+            // Directories count the errors below them, so the root has them all
+            error_count: root.error_count,
+            denied_count: root.denied_count,
+            scan_timings: root.scan_timings.clone(),
+            ..Default::default()
+        };
+        visit(root, 0, &mut stats);
+        stats
+    }
+
+    /// Mean size of the files, or 0 without files
+    pub fn average_file_size(&self) -> u64 {
+        self.file_bytes.checked_div(self.files).unwrap_or(0)
+    }
+}
+
+/// An entry moved to the trash during this session, kept so it can be restored
+#[derive(Debug, Clone)]
+pub struct TrashedEntry {
+    /// Absolute path the entry had on disk, as recorded by the trash
+    pub original_path: PathBuf,
+    /// Path of the directory node it was removed from
+    pub parent_path: PathBuf,
+    pub node: Rc<RefCell<FileNode>>,
+}
+
+/// An entry hidden for the rest of the session, kept so it can be put back
+#[derive(Debug, Clone)]
+pub struct ExcludedEntry {
+    /// Path of the directory node it was removed from
+    pub parent_path: PathBuf,
+    pub node: Rc<RefCell<FileNode>>,
+}
+
+/// Hint shown when entries couldn't be read for lack of permission
+fn permission_hint(denied_count: usize) -> Option<String> {
+    (denied_count > 0).then(|| {
+        format!(
+            "{} items unreadable (permission denied); try running with elevated privileges",
+            denied_count
+        )
+    })
+}
+
+/// Order two entries by `mode`, smallest first
+fn compare_nodes(a: &FileNode, b: &FileNode, mode: SortMode, inodes: bool) -> Ordering {
+    match mode {
+        SortMode::Size => a.metric(inodes).cmp(&b.metric(inodes)),
+        SortMode::ModifiedTime => a.modified_time.cmp(&b.modified_time),
+        SortMode::ItemCount => a.child_count().cmp(&b.child_count()),
+        SortMode::Name => natural_cmp(&a.name, &b.name),
+    }
+}
 
 /// Application State
 pub struct App {
-    #[allow(dead_code)] // Kept for potential navigation reset feature
     pub root: Rc<RefCell<FileNode>>,
     pub current_node: Rc<RefCell<FileNode>>,
     pub path_history: Vec<Rc<RefCell<FileNode>>>,
+    /// Directories left with `go_up`, most recent last, for `go_forward` to return to
+    pub forward_history: Vec<PathBuf>,
     pub state: ListState,
+    pub list_height: usize,
     pub args: Args,
+    pub theme: Theme,
     pub status_message: Option<String>,
-    pub show_help: bool,
+    pub overlay: Option<Overlay>,
+    /// Lines scrolled past at the top of the open overlay
+    pub overlay_scroll: u16,
+    pub overlay_max_scroll: u16,
+    /// Screen area of the open overlay, as last drawn
+    pub overlay_area: Rect,
+    /// Screen area of each column label in the list header, as last drawn
+    pub header_columns: Vec<(Rect, ColumnKind)>,
+    pub recent_files: Vec<RecentFile>,
+    pub histogram: Vec<HistogramBucket>,
+    pub details: Option<NodeDetails>,
+    /// Directory whose children the peek overlay lists
+    pub peek: Option<Rc<RefCell<FileNode>>>,
+    /// Statistics of the whole tree, computed when first shown and dropped when the tree changes
+    pub tree_stats: Option<TreeStats>,
+    pub confirm_delete: bool,
+    /// Open footer prompt, if any
+    pub prompt: Option<Prompt>,
+    pub marked: HashSet<PathBuf>,
+    pub marked_size: u64,
+    /// Bytes deleted during this session, shown against `--target`
+    pub freed_size: u64,
+    /// Entries trashed during this session, most recent last
+    pub trash_history: Vec<TrashedEntry>,
+    /// Entries hidden from the view and the totals during this session, most recent last
+    pub excluded: Vec<ExcludedEntry>,
+    pub bookmarks: HashMap<char, PathBuf>,
+    pub previous_sizes: HashMap<PathBuf, u64>,
+    /// Size of every path in the `--compare` snapshot, subtrees included
+    pub snapshot_sizes: Option<HashMap<PathBuf, u64>>,
+    pub pending_key: Option<PendingKey>,
     pub sort_mode: SortMode,
     pub sort_ascending: bool,
+    pub dir_grouping: DirGrouping,
+    /// Sort key for directories only (`--dir-sort`), listed before files
+    pub dir_sort: Option<SortMode>,
+    /// Sort key for files only (`--file-sort`)
+    pub file_sort: Option<SortMode>,
+    pub bar_relative_to_max: bool,
+    /// Show chains of single-child directories as one row and enter them in one step
+    pub collapse_chains: bool,
+    pub columns: Vec<ColumnKind>,
+    /// Name first, other columns on the right (`--size-right`)
+    pub size_right: bool,
+    pub percent_precision: usize,
+    pub inode_view: bool,
+    /// What percentages, and bars unless scaled to the largest entry, are shares of
+    pub percent_basis: PercentBasis,
+    /// Capacity and free space of the disk holding the root, updated on refresh
+    pub disk_space: Option<DiskSpace>,
+    /// Show the total of the rows from the top through the selected one in the footer
+    pub show_cumulative: bool,
+    pub cumulative_metric: Option<u64>,
+    /// List only directories; loose files are summarized in one row
+    pub dirs_only: bool,
+    /// List what the display filters leave out instead of what they keep
+    pub invert_filter: bool,
+    /// Show how the current directory compares to the whole scan above the list
+    pub show_root_bar: bool,
+    /// Show the bar column when `columns` has one; hiding it gives its width to the name
+    pub show_bar: bool,
+    /// Scan still adding entries to the tree with `--progressive`
+    pub live_scan: Option<LiveScan>,
 }
 
+/// Closure run over a freshly built tree before the app uses it, see [`App::with_tree_hook`]
+pub type TreeHook = Box<dyn FnOnce(&Rc<RefCell<FileNode>>)>;
+
 impl App {
     pub fn new(root: Rc<RefCell<FileNode>>, args: Args) -> Self {
+        Self::with_tree_hook(root, args, None)
+    }
+
+    /// Like [`App::new`], running `on_tree_built` over the tree first
+    ///
+    /// The hook can annotate, rename or remove nodes before anything is sorted or
+    /// selected. It only sees the tree it's given here, not the trees built by later
+    /// refreshes; a hook that changes sizes should keep the totals above consistent.
+    ///
+    /// ```
+    /// use clap::Parser;
+    /// use rdu::{App, Args, ScanCache, build_tree};
+    ///
+    /// let args = Args::parse_from(["rdu", "."]);
+    /// let root = build_tree(&args.path, Vec::new(), &args, &ScanCache::default());
+    /// let app = App::with_tree_hook(
+    ///     root,
+    ///     args,
+    ///     Some(Box::new(|root| root.borrow_mut().name = "project".to_string())),
+    /// );
+    /// assert_eq!(app.root.borrow().name, "project");
+    /// ```
+    pub fn with_tree_hook(
+        root: Rc<RefCell<FileNode>>,
+        args: Args,
+        on_tree_built: Option<TreeHook>,
+    ) -> Self {
+        if let Some(hook) = on_tree_built {
+            hook(&root);
+        }
         let current_node = Rc::clone(&root);
+        let disk_space = disk_space(&root.borrow().path);
+        let status_message = permission_hint(root.borrow().denied_count);
+        let default_theme = Theme::default();
+        let theme = Theme {
+            highlight_bg: args.highlight_bg.unwrap_or(default_theme.highlight_bg),
+            highlight_fg: args.highlight_fg.unwrap_or(default_theme.highlight_fg),
+            highlight_mode: args.highlight,
+            // The TUI only runs on a terminal
+            colors: args.color.enabled(true),
+        };
+        let dir_grouping = args.group_dirs;
+        let dir_sort = args.dir_sort;
+        let file_sort = args.file_sort;
+        let inode_view = args.inodes;
+        let dirs_only = args.dirs_only_tree;
+        let size_right = args.size_right;
+        let percent_precision = usize::from(args.percent_precision);
+        let columns = if args.columns.is_empty() {
+            default_columns()
+        } else {
+            args.columns.clone()
+        };
         let mut app = Self {
             root,
             current_node,
             path_history: Vec::new(),
+            forward_history: Vec::new(),
             state: ListState::default(),
+            list_height: 0,
             args,
-            status_message: None,
-            show_help: false,
+            theme,
+            status_message,
+            overlay: None,
+            overlay_scroll: 0,
+            overlay_max_scroll: 0,
+            overlay_area: Rect::default(),
+            header_columns: Vec::new(),
+            recent_files: Vec::new(),
+            histogram: Vec::new(),
+            details: None,
+            peek: None,
+            tree_stats: None,
+            confirm_delete: false,
+            prompt: None,
+            marked: HashSet::new(),
+            marked_size: 0,
+            freed_size: 0,
+            trash_history: Vec::new(),
+            excluded: Vec::new(),
+            bookmarks: HashMap::new(),
+            previous_sizes: HashMap::new(),
+            snapshot_sizes: None,
+            pending_key: None,
             sort_mode: SortMode::Size,
             sort_ascending: false,
+            dir_grouping,
+            dir_sort,
+            file_sort,
+            bar_relative_to_max: false,
+            collapse_chains: false,
+            columns,
+            size_right,
+            percent_precision,
+            percent_basis: PercentBasis::Metric,
+            disk_space,
+            inode_view,
+            show_cumulative: false,
+            cumulative_metric: None,
+            dirs_only,
+            invert_filter: false,
+            show_root_bar: true,
+            show_bar: true,
+            live_scan: None,
         };
         app.sort_current_view();
         let has_children = !app.current_node.borrow().children.is_empty();
         if has_children {
-            app.state.select(Some(0));
+            app.select(Some(0));
         }
         app
     }
 
+    /// Sort the current directory, keeping the selected entry selected
     pub fn sort_current_view(&mut self) {
+        let selected_id = self.selected_node().map(|n| n.borrow().id);
+        self.sort_children(&self.current_node);
+        if let Some(id) = selected_id {
+            self.select_id(id);
+        }
+    }
+
+    /// Sort the children of `node` by the active sort settings
+    fn sort_children(&self, node: &Rc<RefCell<FileNode>>) {
         let sort_mode = self.sort_mode;
         let ascending = self.sort_ascending;
-        let mut node = self.current_node.borrow_mut();
-        node.children.sort_by(|a, b| {
-            let a = a.borrow();
-            let b = b.borrow();
-            let cmp = match sort_mode {
-                SortMode::Size => a.size.cmp(&b.size),
-                SortMode::ModifiedTime => a.modified_time.cmp(&b.modified_time),
-                SortMode::ItemCount => a.child_count().cmp(&b.child_count()),
+        let dir_grouping = self.dir_grouping;
+        let inodes = self.inode_view;
+        let directed = |cmp: Ordering, ascending: bool| if ascending { cmp } else { cmp.reverse() };
+        let mut node = node.borrow_mut();
+
+        if self.dir_sort.is_some() || self.file_sort.is_some() {
+            // Directories and files use their own keys, in that mode's default direction
+            let (dir_mode, dir_ascending) = self
+                .dir_sort
+                .map_or((sort_mode, ascending), |m| (m, m.default_ascending()));
+            let (file_mode, file_ascending) = self
+                .file_sort
+                .map_or((sort_mode, ascending), |m| (m, m.default_ascending()));
+            let (mut dirs, mut files): (Vec<_>, Vec<_>) = std::mem::take(&mut node.children)
+                .into_iter()
+                .partition(|c| c.borrow().is_dir);
+            dirs.sort_by(|a, b| {
+                let cmp = compare_nodes(&a.borrow(), &b.borrow(), dir_mode, inodes);
+                directed(cmp, dir_ascending)
+            });
+            files.sort_by(|a, b| {
+                let cmp = compare_nodes(&a.borrow(), &b.borrow(), file_mode, inodes);
+                directed(cmp, file_ascending)
+            });
+            node.children = if dir_grouping == DirGrouping::Last {
+                files.into_iter().chain(dirs).collect()
+            } else {
+                dirs.into_iter().chain(files).collect()
             };
-            if ascending { cmp } else { cmp.reverse() }
-        });
+        } else {
+            node.children.sort_by(|a, b| {
+                let a = a.borrow();
+                let b = b.borrow();
+                let group = match dir_grouping {
+                    DirGrouping::Mixed => Ordering::Equal,
+                    DirGrouping::First => b.is_dir.cmp(&a.is_dir),
+                    DirGrouping::Last => a.is_dir.cmp(&b.is_dir),
+                };
+                group.then(directed(
+                    compare_nodes(&a, &b, sort_mode, inodes),
+                    ascending,
+                ))
+            });
+        }
     }
 
     pub fn toggle_sort_by_size(&mut self) {
-        if self.sort_mode == SortMode::Size {
+        self.toggle_sort(SortMode::Size);
+    }
+
+    pub fn toggle_sort_by_mtime(&mut self) {
+        self.toggle_sort(SortMode::ModifiedTime);
+    }
+
+    pub fn toggle_sort_by_count(&mut self) {
+        self.toggle_sort(SortMode::ItemCount);
+    }
+
+    /// Sort by `mode` in its default direction, or reverse the direction if already sorted by it
+    pub fn toggle_sort(&mut self, mode: SortMode) {
+        if self.sort_mode == mode {
             self.sort_ascending = !self.sort_ascending;
         } else {
-            self.sort_mode = SortMode::Size;
-            self.sort_ascending = false;
+            self.sort_mode = mode;
+            self.sort_ascending = mode.default_ascending();
         }
         self.sort_current_view();
         self.status_message = Some(format!(
@@ -69,13 +521,18 @@ impl App {
         ));
     }
 
-    pub fn toggle_sort_by_mtime(&mut self) {
-        if self.sort_mode == SortMode::ModifiedTime {
-            self.sort_ascending = !self.sort_ascending;
-        } else {
-            self.sort_mode = SortMode::ModifiedTime;
-            self.sort_ascending = false;
-        }
+    /// Column whose header label is at `position`, as last drawn
+    pub fn header_column_at(&self, position: Position) -> Option<ColumnKind> {
+        self.header_columns
+            .iter()
+            .find(|(area, _)| area.contains(position))
+            .map(|(_, column)| *column)
+    }
+
+    /// Switch to the next sort mode in its default direction
+    pub fn cycle_sort(&mut self) {
+        self.sort_mode = self.sort_mode.next();
+        self.sort_ascending = self.sort_mode.default_ascending();
         self.sort_current_view();
         self.status_message = Some(format!(
             "Sort: {} {}",
@@ -84,36 +541,535 @@ impl App {
         ));
     }
 
-    pub fn toggle_sort_by_count(&mut self) {
-        if self.sort_mode == SortMode::ItemCount {
-            self.sort_ascending = !self.sort_ascending;
+    /// Go back to the biggest-first view: size descending, startup grouping and metric,
+    /// every entry listed and the top row selected
+    pub fn reset_view(&mut self) {
+        self.sort_mode = SortMode::Size;
+        self.sort_ascending = false;
+        self.dir_grouping = self.args.group_dirs;
+        self.inode_view = self.args.inodes;
+        self.dirs_only = self.args.dirs_only_tree;
+        self.invert_filter = false;
+        self.sort_current_view();
+        *self.state.offset_mut() = 0;
+        if self.current_children().is_empty() {
+            self.select(None);
         } else {
-            self.sort_mode = SortMode::ItemCount;
-            self.sort_ascending = false;
+            self.select(Some(0));
         }
+        self.status_message = Some("View reset: size desc".to_string());
+    }
+
+    /// Cycle between mixing, grouping directories first and grouping them last
+    pub fn toggle_dir_grouping(&mut self) {
+        self.dir_grouping = self.dir_grouping.next();
         self.sort_current_view();
-        self.status_message = Some(format!(
-            "Sort: {} {}",
-            self.sort_mode.name(),
-            if self.sort_ascending { "asc" } else { "desc" }
-        ));
+        self.status_message = Some(format!("Grouping: {}", self.dir_grouping.name()));
+    }
+
+    /// Open an overlay, or close it if it is already shown
+    pub fn toggle_overlay(&mut self, overlay: Overlay) {
+        if self.overlay == Some(overlay) {
+            self.overlay = None;
+            return;
+        }
+        match overlay {
+            Overlay::RecentFiles => {
+                self.recent_files = self.collect_recent_files(RECENT_FILES_LIMIT);
+            }
+            Overlay::Histogram => self.histogram = self.collect_size_histogram(),
+            Overlay::Details => {
+                let Some(node) = self.selected_node() else {
+                    return;
+                };
+                self.details = Some(NodeDetails::from_node(&node.borrow()));
+            }
+            Overlay::Peek => {
+                let Some(node) = self.selected_node() else {
+                    return;
+                };
+                if !node.borrow().is_dir {
+                    self.status_message =
+                        Some(format!("{} is not a directory", node.borrow().name));
+                    return;
+                }
+                self.sort_children(&node);
+                self.peek = Some(node);
+            }
+            Overlay::Stats => {
+                let root = &self.root;
+                self.tree_stats
+                    .get_or_insert_with(|| TreeStats::from_tree(&root.borrow()));
+            }
+            Overlay::Help => {}
+        }
+        self.overlay = Some(overlay);
+        self.overlay_scroll = 0;
+    }
+
+    /// Scroll the open overlay by `delta` lines, staying within its content
+    pub fn scroll_overlay(&mut self, delta: i32) {
+        let scroll =
+            (i32::from(self.overlay_scroll) + delta).clamp(0, self.overlay_max_scroll.into());
+        self.overlay_scroll = u16::try_from(scroll).unwrap_or(0);
+    }
+
+    /// The most recently modified files below the current directory, newest first
+    ///
+    /// Files without a modification time are sorted last.
+    pub fn collect_recent_files(&self, limit: usize) -> Vec<RecentFile> {
+        fn collect(node: &FileNode, base: &Path, files: &mut Vec<RecentFile>) {
+            for child in &node.children {
+                let child = child.borrow();
+                if child.is_dir {
+                    collect(&child, base, files);
+                } else {
+                    files.push(RecentFile {
+                        relative_path: child
+                            .path
+                            .strip_prefix(base)
+                            .unwrap_or(&child.path)
+                            .to_path_buf(),
+                        size: child.size,
+                        modified_time: child.modified_time,
+                    });
+                }
+            }
+        }
+
+        let current = self.current_node.borrow();
+        let mut files = Vec::new();
+        collect(&current, &current.path, &mut files);
+        // Reversing Option's ordering puts newer times first and None last
+        files.sort_by_key(|f| Reverse(f.modified_time));
+        files.truncate(limit);
+        files
+    }
+
+    /// Count files below the current directory by size range
+    pub fn collect_size_histogram(&self) -> Vec<HistogramBucket> {
+        fn collect(node: &FileNode, buckets: &mut [HistogramBucket]) {
+            for child in &node.children {
+                let child = child.borrow();
+                if child.is_dir {
+                    collect(&child, buckets);
+                    continue;
+                }
+                let idx = HISTOGRAM_BOUNDS
+                    .iter()
+                    .position(|&(_, upper)| child.size < upper)
+                    .unwrap_or(HISTOGRAM_BOUNDS.len() - 1);
+                if let Some(bucket) = buckets.get_mut(idx) {
+                    bucket.count += 1;
+                    bucket.total_size += child.size;
+                }
+            }
+        }
+
+        let mut buckets: Vec<HistogramBucket> = HISTOGRAM_BOUNDS
+            .iter()
+            .map(|&(label, _)| HistogramBucket {
+                label,
+                count: 0,
+                total_size: 0,
+            })
+            .collect();
+        collect(&self.current_node.borrow(), &mut buckets);
+        buckets
+    }
+
+    /// Check size propagation across the whole tree and report the result in the footer
+    pub fn verify_tree(&mut self) {
+        if self.args.disk_usage {
+            self.status_message = Some(
+                "Cannot verify with --disk-usage: directories count their own size".to_string(),
+            );
+            return;
+        }
+        self.status_message = Some(match verify_sizes(&self.root.borrow()) {
+            Ok(()) => "Tree OK: all directory sizes match their contents".to_string(),
+            Err(mismatch) => format!("Size mismatch at {}", mismatch),
+        });
+    }
+
+    /// Switch bars between percent of the directory total and fraction of the largest entry
+    pub fn toggle_bar_scale(&mut self) {
+        self.bar_relative_to_max = !self.bar_relative_to_max;
+        self.status_message = Some(
+            if self.bar_relative_to_max {
+                "Bars: relative to largest entry"
+            } else {
+                "Bars: relative to directory total"
+            }
+            .to_string(),
+        );
+    }
+
+    /// Cycle percentages between shares of the view's metric, of the item count and of the disk
+    ///
+    /// The disk is skipped when its capacity can't be queried.
+    pub fn toggle_percent_basis(&mut self) {
+        self.percent_basis = match self.percent_basis {
+            PercentBasis::Metric => PercentBasis::Items,
+            PercentBasis::Items if self.disk_space.is_some() => PercentBasis::Disk,
+            PercentBasis::Items | PercentBasis::Disk => PercentBasis::Metric,
+        };
+        self.status_message = Some(match (self.percent_basis, self.disk_space) {
+            (PercentBasis::Items, _) => "Percent: share of the directory's items".to_string(),
+            (PercentBasis::Disk, Some(space)) => {
+                format!("Percent: share of the disk ({})", format_size(space.total))
+            }
+            _ if self.inode_view => {
+                "Percent: share of the directory's items (inode view)".to_string()
+            }
+            _ => "Percent: share of the directory's size".to_string(),
+        });
     }
 
+    /// Capacity of the disk when percentages and bars are shares of it
+    pub fn disk_basis_total(&self) -> Option<u64> {
+        self.disk_space
+            .filter(|_| self.percent_basis == PercentBasis::Disk)
+            .map(|space| space.total)
+    }
+
+    /// Show or hide the running total of the rows up to the selected one
+    pub fn toggle_cumulative(&mut self) {
+        self.show_cumulative = !self.show_cumulative;
+        self.update_cumulative_metric();
+    }
+
+    /// Recompute the running total; done on selection changes rather than every frame
+    fn update_cumulative_metric(&mut self) {
+        self.cumulative_metric = match self.state.selected() {
+            Some(index) if self.show_cumulative => Some(
+                self.current_children()
+                    .iter()
+                    .take(index + 1)
+                    .map(|c| c.borrow().metric(self.inode_view))
+                    .sum(),
+            ),
+            _ => None,
+        };
+    }
+
+    pub fn toggle_collapse_chains(&mut self) {
+        self.collapse_chains = !self.collapse_chains;
+        self.status_message = Some(
+            if self.collapse_chains {
+                "Single-child directory chains: collapsed"
+            } else {
+                "Single-child directory chains: expanded"
+            }
+            .to_string(),
+        );
+    }
+
+    /// Show or hide the share-of-root bar above the list
+    pub fn toggle_root_bar(&mut self) {
+        self.show_root_bar = !self.show_root_bar;
+    }
+
+    /// Hide or show the bar column, leaving its width to the name while hidden
+    pub fn toggle_bar(&mut self) {
+        if !self.columns.contains(&ColumnKind::Bar) {
+            self.status_message = Some("No bar column (--columns)".to_string());
+            return;
+        }
+        self.show_bar = !self.show_bar;
+        self.status_message = Some(
+            if self.show_bar {
+                "Bar column: shown"
+            } else {
+                "Bar column: hidden"
+            }
+            .to_string(),
+        );
+    }
+
+    /// Switch between listing every entry and listing directories only
+    pub fn toggle_dirs_only(&mut self) {
+        if self.args.dirs_only_tree {
+            self.status_message = Some("Files weren't kept (--dirs-only-tree)".to_string());
+            return;
+        }
+        let selected_id = self.selected_node().map(|n| n.borrow().id);
+        self.dirs_only = !self.dirs_only;
+        *self.state.offset_mut() = 0;
+        if self.current_children().is_empty() {
+            self.select(None);
+        } else if !selected_id.is_some_and(|id| self.select_id(id)) {
+            self.select(Some(0));
+        }
+        self.status_message = Some(
+            if self.dirs_only {
+                "View: directories only"
+            } else {
+                "View: all entries"
+            }
+            .to_string(),
+        );
+    }
+
+    /// Swap what the display filters keep for what they leave out, e.g. only the files
+    /// below `--min-size`
+    pub fn toggle_invert_filter(&mut self) {
+        if self.args.dirs_only_tree {
+            self.status_message = Some("Files weren't kept (--dirs-only-tree)".to_string());
+            return;
+        }
+        if !self.filters_active() {
+            self.status_message = Some("No display filter to invert".to_string());
+            return;
+        }
+        let selected_id = self.selected_node().map(|n| n.borrow().id);
+        self.invert_filter = !self.invert_filter;
+        *self.state.offset_mut() = 0;
+        if self.current_children().is_empty() {
+            self.select(None);
+        } else if !selected_id.is_some_and(|id| self.select_id(id)) {
+            self.select(Some(0));
+        }
+        self.status_message = Some(
+            if self.invert_filter {
+                "Filter inverted: showing what it leaves out"
+            } else {
+                "Filter: showing what it keeps"
+            }
+            .to_string(),
+        );
+    }
+
+    /// Whether any display filter is on, so listing can leave entries out
+    fn filters_active(&self) -> bool {
+        self.dirs_only || self.args.min_size > 0 || !self.args.list_ext.is_empty()
+    }
+
+    /// Whether the list shows what the display filters leave out
+    pub fn filter_inverted(&self) -> bool {
+        self.invert_filter && self.filters_active()
+    }
+
+    /// Whether `node` is listed: it passes every active display filter, or fails one
+    /// while the filter is inverted
+    ///
+    /// `--list-ext` only applies to files, so directories stay listed both ways unless
+    /// another filter decides.
+    fn is_listed(&self, node: &FileNode) -> bool {
+        let mut checks = [
+            self.dirs_only.then_some(node.is_dir),
+            (self.args.min_size > 0).then_some(node.size >= self.args.min_size),
+            (!self.args.list_ext.is_empty() && !node.is_dir)
+                .then(|| extension_matches(&node.path, &self.args.list_ext)),
+        ]
+        .into_iter()
+        .flatten()
+        .peekable();
+        if checks.peek().is_none() {
+            return true;
+        }
+        let passes = checks.all(|passes| passes);
+        passes != self.invert_filter
+    }
+
+    /// Name shown for a row: the whole `a/b/c` chain when chains are collapsed
+    pub fn display_name(&self, node: &Rc<RefCell<FileNode>>) -> String {
+        let mut name = node.borrow().name.clone();
+        if self.collapse_chains && node.borrow().is_dir {
+            for link in single_child_chain(node) {
+                name.push('/');
+                name.push_str(&link.borrow().name);
+            }
+        }
+        name
+    }
+
+    /// Children of the current directory that are listed, in sort order
+    ///
+    /// With `--max-items` only the first entries are listed; see [`App::hidden_children`].
+    /// In the directory-only view files are left out; see [`App::loose_files`].
     pub fn current_children(&self) -> Vec<Rc<RefCell<FileNode>>> {
-        self.current_node.borrow().children.clone()
+        let mut children = self.shown_children();
+        children.truncate(self.max_items().unwrap_or(usize::MAX));
+        children
+    }
+
+    /// Number and total (in the active metric) of children cut off by `--max-items`
+    pub fn hidden_children(&self) -> Option<(usize, u64)> {
+        let limit = self.max_items()?;
+        let children = self.shown_children();
+        let hidden = children.get(limit..).filter(|rest| !rest.is_empty())?;
+        let total = hidden
+            .iter()
+            .map(|c| c.borrow().metric(self.inode_view))
+            .sum();
+        Some((hidden.len(), total))
+    }
+
+    /// Number and total (in the active metric) of the files left out by the directory-only
+    /// view, including those folded into the directory by `--dirs-only-tree`
+    pub fn loose_files(&self) -> Option<(usize, u64)> {
+        if !self.dirs_only || self.filter_inverted() {
+            return None;
+        }
+        let node = self.current_node.borrow();
+        let folded = (
+            usize::try_from(node.folded_files).unwrap_or(usize::MAX),
+            node.folded_metric(self.inode_view),
+        );
+        let (count, total) = node
+            .children
+            .iter()
+            .map(|c| c.borrow())
+            .filter(|c| !c.is_dir)
+            .fold(folded, |(count, total), c| {
+                (count + 1, total + c.metric(self.inode_view))
+            });
+        (count > 0).then_some((count, total))
+    }
+
+    /// Children in sort order, before `--max-items` applies
+    fn shown_children(&self) -> Vec<Rc<RefCell<FileNode>>> {
+        self.current_node
+            .borrow()
+            .children
+            .iter()
+            .filter(|c| self.is_listed(&c.borrow()))
+            .cloned()
+            .collect()
+    }
+
+    fn max_items(&self) -> Option<usize> {
+        self.args
+            .max_items
+            .map(|n| usize::try_from(n).unwrap_or(usize::MAX))
+    }
+
+    /// Select a row and scroll the list so it stays within the visible area
+    pub fn select(&mut self, index: Option<usize>) {
+        self.state.select(index);
+        self.update_cumulative_metric();
+        self.scroll_to_selected();
+    }
+
+    /// Scroll the list so the selected row is within the visible area
+    pub fn scroll_to_selected(&mut self) {
+        let Some(index) = self.state.selected() else {
+            return;
+        };
+        if self.list_height == 0 {
+            return;
+        }
+        let offset = self.state.offset();
+        if index < offset {
+            *self.state.offset_mut() = index;
+        } else if index >= offset + self.list_height {
+            *self.state.offset_mut() = index + 1 - self.list_height;
+        }
+    }
+
+    /// Select the child with the given node id, returning whether it was found
+    pub fn select_id(&mut self, id: u64) -> bool {
+        let idx = self
+            .current_children()
+            .iter()
+            .position(|c| c.borrow().id == id);
+        if idx.is_some() {
+            self.select(idx);
+        }
+        idx.is_some()
+    }
+
+    /// Columns in display order, with the name moved first in the `--size-right` layout
+    /// and the bar left out while it's hidden
+    pub fn display_columns(&self) -> Vec<ColumnKind> {
+        let shown = self
+            .columns
+            .iter()
+            .copied()
+            .filter(|c| self.show_bar || *c != ColumnKind::Bar);
+        if !self.size_right {
+            return shown.collect();
+        }
+        let (name, rest): (Vec<_>, Vec<_>) = shown.partition(|c| *c == ColumnKind::Name);
+        name.into_iter().chain(rest).collect()
+    }
+
+    /// Get the currently selected child, if any
+    pub fn selected_node(&self) -> Option<Rc<RefCell<FileNode>>> {
+        let idx = self.state.selected()?;
+        self.current_children().get(idx).cloned()
     }
 
     pub fn current_path(&self) -> PathBuf {
         self.current_node.borrow().path.clone()
     }
 
+    /// Total size of the current directory (already propagated during the scan)
     pub fn current_total_size(&self) -> u64 {
-        self.current_node
-            .borrow()
+        self.current_node.borrow().size
+    }
+
+    /// Total of the active metric (bytes or items) for the current directory
+    pub fn current_total_metric(&self) -> u64 {
+        self.current_node.borrow().metric(self.inode_view)
+    }
+
+    pub fn root_total_metric(&self) -> u64 {
+        self.root.borrow().metric(self.inode_view)
+    }
+
+    /// Total that percentages and bars are relative to: the directories' share
+    /// in the directory-only view, the whole directory otherwise
+    pub fn list_total_metric(&self) -> u64 {
+        match self.loose_files() {
+            Some((_, files)) => self.current_total_metric().saturating_sub(files),
+            None => self.current_total_metric(),
+        }
+    }
+
+    /// Whether the percent column is computed from item counts
+    pub fn percent_inodes(&self) -> bool {
+        match self.percent_basis {
+            PercentBasis::Metric => self.inode_view,
+            PercentBasis::Items => true,
+            PercentBasis::Disk => false,
+        }
+    }
+
+    /// Total the percent column is relative to, like [`App::list_total_metric`] but in
+    /// the percent column's metric
+    pub fn percent_total(&self) -> u64 {
+        if let Some(total) = self.disk_basis_total() {
+            return total;
+        }
+        let inodes = self.percent_inodes();
+        // Walks the children itself, so check it once rather than per child
+        let loose = self.loose_files().is_some();
+        let node = self.current_node.borrow();
+        let files: u64 = node
             .children
             .iter()
-            .map(|c| c.borrow().size)
-            .sum()
+            .map(|c| c.borrow())
+            .filter(|c| loose && !c.is_dir)
+            .map(|c| c.metric(inodes))
+            .sum();
+        node.metric(inodes)
+            .saturating_sub(files + node.folded_metric(inodes))
+    }
+
+    /// Switch between byte sizes and item (inode) counts for sorting and bars
+    pub fn toggle_inode_view(&mut self) {
+        self.inode_view = !self.inode_view;
+        self.sort_current_view();
+        self.status_message = Some(
+            if self.inode_view {
+                "View: item counts (inodes)"
+            } else {
+                "View: sizes"
+            }
+            .to_string(),
+        );
     }
 
     pub fn next(&mut self) {
@@ -129,7 +1085,7 @@ impl App {
             None => 0,
         };
         if !children.is_empty() {
-            self.state.select(Some(i));
+            self.select(Some(i));
         }
     }
 
@@ -150,47 +1106,61 @@ impl App {
             None => 0,
         };
         if !children.is_empty() {
-            self.state.select(Some(i));
+            self.select(Some(i));
+        }
+    }
+
+    /// Rows moved by a page jump: the visible list height, before the first draw a fixed guess
+    fn page_size(&self) -> usize {
+        if self.list_height == 0 {
+            10
+        } else {
+            self.list_height
         }
     }
 
+    /// Move the selection and the top of the view down by a full page
     pub fn page_down(&mut self) {
-        let children = self.current_children();
-        if children.is_empty() {
+        let len = self.current_children().len();
+        if len == 0 {
             return;
         }
-        let page_size = 10;
+        let page = self.page_size();
         let i = match self.state.selected() {
-            Some(i) => (i + page_size).min(children.len() - 1),
+            Some(i) => (i + page).min(len - 1),
             None => 0,
         };
-        self.state.select(Some(i));
+        let offset = (self.state.offset() + page).min(len.saturating_sub(page));
+        *self.state.offset_mut() = offset;
+        self.select(Some(i));
     }
 
+    /// Move the selection and the top of the view up by a full page
     pub fn page_up(&mut self) {
-        let children = self.current_children();
-        if children.is_empty() {
+        if self.current_children().is_empty() {
             return;
         }
-        let page_size = 10;
+        let page = self.page_size();
         let i = match self.state.selected() {
-            Some(i) => i.saturating_sub(page_size),
+            Some(i) => i.saturating_sub(page),
             None => 0,
         };
-        self.state.select(Some(i));
+        let offset = self.state.offset().saturating_sub(page);
+        *self.state.offset_mut() = offset;
+        self.select(Some(i));
     }
 
     pub fn go_to_first(&mut self) {
         let children = self.current_children();
         if !children.is_empty() {
-            self.state.select(Some(0));
+            self.select(Some(0));
         }
     }
 
     pub fn go_to_last(&mut self) {
         let children = self.current_children();
         if !children.is_empty() {
-            self.state.select(Some(children.len() - 1));
+            self.select(Some(children.len() - 1));
         }
     }
 
@@ -204,52 +1174,1029 @@ impl App {
             let selected = Rc::clone(child);
             if selected.borrow().is_dir {
                 self.path_history.push(Rc::clone(&self.current_node));
-                self.current_node = selected;
+                let mut target = selected;
+                if self.collapse_chains {
+                    // Keep every link in the history so sizes still propagate upwards
+                    for link in single_child_chain(&target) {
+                        self.path_history.push(target);
+                        target = link;
+                    }
+                }
+                self.current_node = target;
+                self.trim_forward_history();
+                self.previous_sizes.clear();
+                *self.state.offset_mut() = 0;
                 self.sort_current_view();
                 let new_children = self.current_children();
                 if new_children.is_empty() {
-                    self.state.select(None);
+                    self.select(None);
                 } else {
-                    self.state.select(Some(0));
+                    self.select(Some(0));
                 }
             }
         }
     }
 
-    /// Go up one level
+    /// Go up one level, keeping the directory we came from selected
+    ///
+    /// With collapsed chains, the single-child directories in between are skipped.
     pub fn go_up(&mut self) {
-        if let Some(parent) = self.path_history.pop() {
+        if let Some(mut parent) = self.path_history.pop() {
+            self.forward_history.push(self.current_path());
+            let mut left_id = self.current_node.borrow().id;
+            while self.collapse_chains
+                && !single_child_chain(&parent).is_empty()
+                && let Some(grandparent) = self.path_history.pop()
+            {
+                left_id = parent.borrow().id;
+                parent = grandparent;
+            }
             self.current_node = parent;
+            self.previous_sizes.clear();
+            *self.state.offset_mut() = 0;
             self.sort_current_view();
-            let children = self.current_children();
-            if children.is_empty() {
-                self.state.select(None);
-            } else {
-                self.state.select(Some(0));
+            if self.current_children().is_empty() {
+                self.select(None);
+            } else if !self.select_id(left_id) {
+                self.select(Some(0));
             }
         }
     }
 
-    /// Refresh the current directory by rescanning
-    pub fn refresh(&mut self) {
-        self.status_message = Some("Rescanning...".to_string());
-        let path = self.current_path();
-        let new_node = scan_dir(&path, &self.args);
-
-        // Update current node's children
-        let mut current = self.current_node.borrow_mut();
-        current.children = new_node.borrow().children.clone();
-        current.size = new_node.borrow().size;
-        current.error_count = new_node.borrow().error_count;
-        drop(current);
-
-        self.sort_current_view();
-        let children = self.current_children();
-        if children.is_empty() {
-            self.state.select(None);
-        } else {
-            self.state.select(Some(0));
+    /// Return to the directory most recently left with `go_up`
+    pub fn go_forward(&mut self) {
+        let Some(path) = self.forward_history.pop() else {
+            self.status_message = Some("Nothing to go forward to".to_string());
+            return;
+        };
+        if !self.navigate_to(&path) {
+            self.forward_history.clear();
+            self.status_message = Some(format!("{} no longer exists in the tree", path.display()));
         }
-        self.status_message = Some("Refresh complete!".to_string());
+    }
+
+    /// Drop forward entries passed on the way down, or all of them once we leave their branch
+    fn trim_forward_history(&mut self) {
+        let current = self.current_path();
+        while self
+            .forward_history
+            .last()
+            .is_some_and(|path| current.starts_with(path))
+        {
+            self.forward_history.pop();
+        }
+        if self
+            .forward_history
+            .last()
+            .is_some_and(|path| !path.starts_with(&current))
+        {
+            self.forward_history.clear();
+        }
+    }
+
+    /// Add the entries a `--progressive` scan found since the last call, keeping the selection
+    pub fn poll_live_scan(&mut self) {
+        let Some(scan) = self.live_scan.as_mut() else {
+            return;
+        };
+        let added = scan.poll();
+        if scan.is_done() {
+            let complete = format!("Scan complete: {} items", scan.items());
+            self.status_message = Some(match permission_hint(self.root.borrow().denied_count) {
+                Some(hint) => format!("{}; {}", complete, hint),
+                None => complete,
+            });
+            self.live_scan = None;
+        }
+        if added == 0 {
+            return;
+        }
+        self.tree_stats = None;
+        self.sort_current_view();
+        if self.state.selected().is_none() && !self.current_children().is_empty() {
+            self.select(Some(0));
+        } else {
+            self.update_cumulative_metric();
+        }
+    }
+
+    /// Whether a `--progressive` scan is still running, telling the user to wait if so
+    ///
+    /// Changing the tree under a running scan would leave its totals wrong.
+    fn scan_running(&mut self) -> bool {
+        if self.live_scan.is_some() {
+            self.status_message = Some("Wait for the scan to finish".to_string());
+        }
+        self.live_scan.is_some()
+    }
+
+    /// Whether the current directory can be rescanned, telling the user why not if it can't
+    fn can_refresh(&mut self) -> bool {
+        if self.scan_running() {
+            return false;
+        }
+        if self.args.from_manifest.is_some() {
+            self.status_message = Some("Cannot refresh a tree loaded from a manifest".to_string());
+            return false;
+        }
+        if self.args.stdin && self.path_history.is_empty() {
+            self.status_message = Some("Cannot refresh paths read from stdin".to_string());
+            return false;
+        }
+        true
+    }
+
+    /// Refresh the current directory, skipping the rescan when no directory in it changed
+    ///
+    /// Only directory mtimes are compared, so files rewritten in place without being
+    /// renamed need [`App::force_refresh`].
+    pub fn refresh(&mut self) {
+        if !self.can_refresh() {
+            return;
+        }
+        // Other programs may have used space even when nothing below us changed
+        self.update_disk_space();
+        let cache = ScanCache::from_tree(&self.current_node);
+        if cache.is_unchanged() {
+            self.status_message = Some("No changes detected".to_string());
+            return;
+        }
+        self.rescan(&cache);
+    }
+
+    /// Refresh the current directory by rescanning all of it, whether or not it changed
+    pub fn force_refresh(&mut self) {
+        if self.can_refresh() {
+            self.rescan(&ScanCache::default());
+        }
+    }
+
+    /// Rescan the current directory, reusing the unchanged subdirectories in `cache`
+    ///
+    /// When the current directory was replaced by a file or a link, or removed, the
+    /// closest ancestor that is still a directory is rescanned instead.
+    fn rescan(&mut self, cache: &ScanCache) {
+        let replaced = self.leave_replaced_directories();
+        if self.path_history.is_empty()
+            && !fs::metadata(self.current_path()).is_ok_and(|m| m.is_dir())
+        {
+            self.status_message = Some(format!(
+                "{} is no longer a directory",
+                self.current_path().display()
+            ));
+            return;
+        }
+        self.status_message = Some("Rescanning...".to_string());
+        let path = self.current_path();
+        let selected = self.selected_node().map(|n| {
+            let n = n.borrow();
+            (n.id, n.path.clone())
+        });
+
+        // Remember sizes so the view can show what grew or shrank
+        self.previous_sizes = self
+            .current_children()
+            .iter()
+            .map(|c| {
+                let c = c.borrow();
+                (c.path.clone(), c.size)
+            })
+            .collect();
+
+        let new_node = scan_dir_cached(&path, &self.args, cache);
+        self.update_disk_space();
+
+        // Update current node's children
+        let mut current = self.current_node.borrow_mut();
+        let old_totals = current.totals();
+        let old_count = current.item_count;
+        current.children = new_node.borrow().children.clone();
+        current.modified_time = new_node.borrow().modified_time;
+        current.partial = new_node.borrow().partial;
+        current.folded_files = new_node.borrow().folded_files;
+        current.folded_size = new_node.borrow().folded_size;
+        if Rc::ptr_eq(&self.current_node, &self.root) {
+            current.scan_timings = new_node.borrow().scan_timings.clone();
+        }
+        drop(current);
+        self.update_current_totals(old_totals, new_node.borrow().totals());
+        self.update_current_item_count(old_count, new_node.borrow().item_count);
+        self.update_current_error_counts(&new_node.borrow());
+        self.exclude_again_below(&path);
+        // A full rescan may have read the last directories an estimate left out
+        if self.root.borrow().partial {
+            update_partial(&self.root, &HashSet::new());
+        }
+        self.tree_stats = None;
+
+        self.state.select(None);
+        self.sort_current_view();
+
+        // Unchanged directories are reused and keep their id; rescanned entries
+        // are new nodes, so fall back to the path for them
+        let children = self.current_children();
+        let idx = selected.and_then(|(id, path)| {
+            children
+                .iter()
+                .position(|c| c.borrow().id == id)
+                .or_else(|| children.iter().position(|c| c.borrow().path == path))
+        });
+        if children.is_empty() {
+            self.select(None);
+        } else {
+            self.select(Some(idx.unwrap_or(0)));
+        }
+        self.status_message = Some(
+            permission_hint(new_node.borrow().denied_count)
+                .unwrap_or_else(|| "Refresh complete!".to_string()),
+        );
+        if let Some(replaced) = replaced {
+            self.status_message = Some(format!("{} is no longer a directory", replaced.display()));
+        }
+    }
+
+    /// Go up out of directories that aren't directories on disk anymore, returning the
+    /// outermost one left
+    ///
+    /// The entry left behind in the new current directory is selected, so the rescan
+    /// keeps it selected as whatever it has become.
+    fn leave_replaced_directories(&mut self) -> Option<PathBuf> {
+        let mut replaced = None;
+        while !self.path_history.is_empty() && !self.is_directory_on_disk(&self.current_path()) {
+            let Some(parent) = self.path_history.pop() else {
+                break;
+            };
+            replaced = Some(Rc::clone(&self.current_node));
+            self.current_node = parent;
+        }
+        let replaced = replaced?;
+        self.forward_history.clear();
+        self.previous_sizes.clear();
+        *self.state.offset_mut() = 0;
+        let id = replaced.borrow().id;
+        if !self.select_id(id) {
+            self.select(None);
+        }
+        let path = replaced.borrow().path.clone();
+        Some(path)
+    }
+
+    /// Whether `path` is a directory on disk, resolving symbolic links the way scans do
+    fn is_directory_on_disk(&self, path: &Path) -> bool {
+        let meta = if self.args.follow_links || self.args.follow_dir_links {
+            fs::metadata(path)
+        } else {
+            fs::symlink_metadata(path)
+        };
+        meta.is_ok_and(|m| m.is_dir())
+    }
+
+    /// Copy the path of the selected entry (or the current directory) to the clipboard
+    pub fn copy_selected_path(&mut self) {
+        let path = match self.selected_node() {
+            Some(node) => node.borrow().path.clone(),
+            None => self.current_path(),
+        };
+        self.copy_text("path", &path.display().to_string());
+    }
+
+    /// Copy the human-readable size of the selected entry (or the current directory) to the clipboard
+    pub fn copy_selected_size(&mut self) {
+        let size = match self.selected_node() {
+            Some(node) => node.borrow().size,
+            None => self.current_total_size(),
+        };
+        self.copy_text("size", &format_size(size));
+    }
+
+    /// Open the prompt for exporting the current listing as `du -h` output
+    pub fn start_export_du(&mut self) {
+        self.prompt = Some(Prompt {
+            kind: PromptKind::ExportDu,
+            input: String::new(),
+        });
+    }
+
+    /// Write the current listing as `du -h` output to `destination`, or copy it to
+    /// the clipboard when `destination` is empty
+    ///
+    /// Entries are listed as shown, including those cut off by `--max-items`. A relative
+    /// `destination` is taken from the directory rdu was started in, and an existing file
+    /// is never overwritten.
+    pub fn export_du(&mut self, destination: &str) {
+        let entries = self.shown_children();
+        let mut listing = Vec::new();
+        // Writing to a Vec can't fail
+        let _ = write_du(&entries, &mut listing);
+
+        let destination = destination.trim();
+        let result = if destination.is_empty() {
+            copy_to_clipboard(
+                &String::from_utf8_lossy(&listing),
+                self.args.print_selection,
+            )
+            .map(|()| "the clipboard".to_string())
+        } else {
+            let path =
+                std::path::absolute(destination).unwrap_or_else(|_| PathBuf::from(destination));
+            fs::OpenOptions::new()
+                .write(true)
+                .create_new(true)
+                .open(&path)
+                .and_then(|mut file| file.write_all(&listing))
+                .map_err(|e| match e.kind() {
+                    io::ErrorKind::AlreadyExists => {
+                        io::Error::new(e.kind(), format!("{} already exists", path.display()))
+                    }
+                    _ => e,
+                })
+                .map(|()| path.display().to_string())
+        };
+        self.status_message = Some(match result {
+            Ok(target) => format!("Exported {} entries to {}", entries.len(), target),
+            Err(e) => format!("Export failed: {}", e),
+        });
+    }
+
+    fn copy_text(&mut self, label: &str, text: &str) {
+        self.status_message = Some(match copy_to_clipboard(text, self.args.print_selection) {
+            Ok(()) => format!("Copied {}: {}", label, text),
+            Err(e) => format!("Copy failed: {}", e),
+        });
+    }
+
+    /// Refresh the current directory if its mtime changed since it was scanned
+    ///
+    /// A directory's mtime only changes when entries are added, removed or renamed
+    /// directly in it, which keeps the check down to a single `stat`.
+    pub fn refresh_if_changed(&mut self) {
+        if self.live_scan.is_some()
+            || self.args.from_manifest.is_some()
+            || (self.args.stdin && self.path_history.is_empty())
+        {
+            return;
+        }
+        let scanned = self.current_node.borrow().modified_time;
+        let on_disk = fs::metadata(self.current_path())
+            .and_then(|m| m.modified())
+            .ok();
+        if on_disk.is_some() && on_disk != scanned {
+            self.rescan(&ScanCache::from_tree(&self.current_node));
+            self.status_message = Some("Auto-refreshed: directory changed".to_string());
+        }
+    }
+
+    /// Query the scanned volume's capacity and free space again
+    fn update_disk_space(&mut self) {
+        self.disk_space = disk_space(&self.root.borrow().path);
+    }
+
+    /// Swap `old` for `new` in the byte totals of the current directory and its ancestors
+    fn update_current_totals(&mut self, old: ByteTotals, new: ByteTotals) {
+        self.current_node.borrow_mut().replace_totals(old, new);
+        for ancestor in &self.path_history {
+            ancestor.borrow_mut().replace_totals(old, new);
+        }
+    }
+
+    /// Replace the current directory's item count and keep ancestor counts consistent
+    fn update_current_item_count(&mut self, old_count: u64, new_count: u64) {
+        self.current_node.borrow_mut().item_count = new_count;
+        for ancestor in &self.path_history {
+            let mut ancestor = ancestor.borrow_mut();
+            ancestor.item_count = (ancestor.item_count + new_count).saturating_sub(old_count);
+        }
+    }
+
+    /// Take the error counts of a rescan of the current directory, keeping ancestor counts consistent
+    fn update_current_error_counts(&mut self, new: &FileNode) {
+        let mut current = self.current_node.borrow_mut();
+        let (old_errors, old_denied) = (current.error_count, current.denied_count);
+        current.error_count = new.error_count;
+        current.denied_count = new.denied_count;
+        drop(current);
+        for ancestor in &self.path_history {
+            let mut ancestor = ancestor.borrow_mut();
+            ancestor.error_count =
+                (ancestor.error_count + new.error_count).saturating_sub(old_errors);
+            ancestor.denied_count =
+                (ancestor.denied_count + new.denied_count).saturating_sub(old_denied);
+        }
+    }
+
+    /// Whether `--read-only` forbids `action`, saying so in the status bar
+    fn refuse_if_read_only(&mut self, action: &str) -> bool {
+        if self.args.read_only {
+            self.status_message = Some(format!("Read-only mode: {} is disabled", action));
+        }
+        self.args.read_only
+    }
+
+    /// Whether the tree came from `--from-manifest`, so `action` would hit whatever local
+    /// path matches an entry recorded elsewhere, saying so in the status bar
+    fn refuse_if_from_manifest(&mut self, action: &str) -> bool {
+        let from_manifest = self.args.from_manifest.is_some();
+        if from_manifest {
+            self.status_message = Some(format!("Cannot {} entries loaded from a manifest", action));
+        }
+        from_manifest
+    }
+
+    /// Ask for confirmation before deleting the selected entry
+    pub fn request_delete(&mut self) {
+        if self.refuse_if_read_only("deleting") || self.refuse_if_from_manifest("delete") {
+            return;
+        }
+        if !self.scan_running() && self.selected_node().is_some() {
+            self.confirm_delete = true;
+        }
+    }
+
+    /// Delete the selected entry from disk and remove it from the tree
+    pub fn delete_selected(&mut self) {
+        self.confirm_delete = false;
+        if self.refuse_if_read_only("deleting") || self.refuse_if_from_manifest("delete") {
+            return;
+        }
+        let Some(idx) = self.state.selected() else {
+            return;
+        };
+        let Some(node) = self.selected_node() else {
+            return;
+        };
+        let method = if self.args.no_trash {
+            DeleteMethod::Permanent
+        } else {
+            DeleteMethod::Trash
+        };
+
+        let (path, name, size, is_dir) = {
+            let node = node.borrow();
+            (node.path.clone(), node.name.clone(), node.size, node.is_dir)
+        };
+        if self.args.dry_run {
+            self.status_message = Some(match self.log_deletion(&path, size, "dry-run") {
+                Ok(()) => format!("Dry run: logged {}", name),
+                Err(e) => format!("Dry run: could not write log: {}", e),
+            });
+            return;
+        }
+        // The trash records absolute paths, so resolve ours while the entry still exists
+        let original_path = absolute_path(&path);
+        if let Err(e) = delete_path(&path, is_dir, method) {
+            self.status_message = Some(format!("Delete failed: {}", e));
+            return;
+        }
+        if method == DeleteMethod::Trash {
+            self.trash_history.push(TrashedEntry {
+                original_path,
+                parent_path: self.current_path(),
+                node: Rc::clone(&node),
+            });
+        }
+        let log_result = self.log_deletion(&path, size, method.log_name());
+        self.freed_size += size;
+        self.update_disk_space();
+
+        // Takes the entry's sizes, item count and read errors out of every directory above it
+        let mut chain = self.path_history.clone();
+        chain.push(Rc::clone(&self.current_node));
+        chain.push(Rc::clone(&node));
+        detach(&chain);
+        let remaining = self.current_children().len();
+        self.tree_stats = None;
+        self.marked.retain(|p| !p.starts_with(&path));
+        self.recompute_marked_size();
+
+        if remaining == 0 {
+            self.select(None);
+        } else {
+            self.select(Some(idx.min(remaining - 1)));
+        }
+        self.status_message = Some(match log_result {
+            Ok(()) => format!("{}: {}", method.description(), name),
+            Err(e) => format!(
+                "{}: {} (could not write log: {})",
+                method.description(),
+                name,
+                e
+            ),
+        });
+    }
+
+    /// Restore the most recently trashed entry and put its node back in the tree
+    pub fn undo_trash(&mut self) {
+        if self.scan_running() || self.refuse_if_read_only("restoring from the trash") {
+            return;
+        }
+        let Some(entry) = self.trash_history.pop() else {
+            self.status_message = Some("Nothing to undo".to_string());
+            return;
+        };
+        if let Err(e) = restore_from_trash(&entry.original_path) {
+            self.status_message = Some(format!("Restore failed: {}", e));
+            // Keep the entry for another try unless the trash no longer has it
+            if e.kind() != io::ErrorKind::NotFound {
+                self.trash_history.push(entry);
+            }
+            return;
+        }
+
+        let (id, name, size) = {
+            let node = entry.node.borrow();
+            (node.id, node.name.clone(), node.size)
+        };
+        self.freed_size = self.freed_size.saturating_sub(size);
+        self.update_disk_space();
+        let Some(chain) = find_path(&self.root, &entry.parent_path) else {
+            self.status_message = Some(format!("Restored {} (refresh to show it)", name));
+            return;
+        };
+        attach(&chain, entry.node);
+        self.tree_stats = None;
+        if entry.parent_path == self.current_path() {
+            self.sort_current_view();
+            self.select_id(id);
+        }
+        self.status_message = Some(format!("Restored {}", name));
+    }
+
+    /// Hide the selected entry for the rest of the session, taking it out of every total above it
+    ///
+    /// Nothing changes on disk; [`App::clear_exclusions`] puts excluded entries back.
+    pub fn exclude_selected(&mut self) {
+        if self.scan_running() {
+            return;
+        }
+        let Some(idx) = self.state.selected() else {
+            return;
+        };
+        let Some(node) = self.selected_node() else {
+            return;
+        };
+        let mut chain = self.path_history.clone();
+        chain.push(Rc::clone(&self.current_node));
+        chain.push(Rc::clone(&node));
+        if detach(&chain).is_none() {
+            return;
+        }
+        let (name, path) = {
+            let node = node.borrow();
+            (node.name.clone(), node.path.clone())
+        };
+        self.excluded.push(ExcludedEntry {
+            parent_path: self.current_path(),
+            node,
+        });
+        self.tree_stats = None;
+        self.marked.retain(|p| !p.starts_with(&path));
+        self.recompute_marked_size();
+
+        let remaining = self.current_children().len();
+        if remaining == 0 {
+            self.select(None);
+        } else {
+            self.select(Some(idx.min(remaining - 1)));
+        }
+        self.status_message = Some(format!(
+            "Excluded {} for this session ({} excluded)",
+            name,
+            self.excluded.len()
+        ));
+    }
+
+    /// Put every excluded entry back in the tree
+    pub fn clear_exclusions(&mut self) {
+        if self.scan_running() {
+            return;
+        }
+        if self.excluded.is_empty() {
+            self.status_message = Some("No excluded entries".to_string());
+            return;
+        }
+        let selected_id = self.selected_node().map(|n| n.borrow().id);
+        let mut restored = 0;
+        let mut missing = 0;
+        // Most recent first, so an entry excluded inside a directory that was excluded
+        // later finds that directory back in the tree
+        while let Some(entry) = self.excluded.pop() {
+            match find_path(&self.root, &entry.parent_path) {
+                Some(chain) => {
+                    attach(&chain, entry.node);
+                    restored += 1;
+                }
+                None => missing += 1,
+            }
+        }
+        self.tree_stats = None;
+        self.sort_current_view();
+        if !selected_id.is_some_and(|id| self.select_id(id)) && !self.current_children().is_empty()
+        {
+            self.select(Some(0));
+        }
+        self.status_message = Some(if missing == 0 {
+            format!("Restored {} excluded entries", restored)
+        } else {
+            format!(
+                "Restored {} excluded entries ({} no longer had a parent; refresh to show them)",
+                restored, missing
+            )
+        });
+    }
+
+    /// Switch what symbolic links count as: the link itself, its target, or nothing
+    ///
+    /// Sizes are updated in place from what the scan recorded, and later rescans count
+    /// links the same way.
+    pub fn cycle_symlink_size(&mut self) {
+        if self.scan_running() {
+            return;
+        }
+        if self.args.dirs_only_tree {
+            self.status_message = Some("Files weren't kept (--dirs-only-tree)".to_string());
+            return;
+        }
+        let mode = self.args.symlink_size.next();
+        self.args.symlink_size = mode;
+        apply_symlink_size(&self.root, mode);
+        for node in self
+            .excluded
+            .iter()
+            .map(|e| &e.node)
+            .chain(self.trash_history.iter().map(|e| &e.node))
+        {
+            apply_symlink_size(node, mode);
+        }
+        self.tree_stats = None;
+        self.recompute_marked_size();
+        self.sort_current_view();
+        self.status_message = Some(format!("Symlinks count: {}", mode.name()));
+    }
+
+    /// Take excluded entries below `dir` out of the tree again after a rescan brought them back
+    fn exclude_again_below(&mut self, dir: &Path) {
+        for entry in &mut self.excluded {
+            let path = entry.node.borrow().path.clone();
+            if !path.starts_with(dir) {
+                continue;
+            }
+            if let Some(node) = find_path(&self.root, &path).and_then(|chain| detach(&chain)) {
+                entry.node = node;
+            }
+        }
+    }
+
+    /// Record a deletion in the `--delete-log` file, if one was given
+    fn log_deletion(&self, path: &Path, size: u64, action: &str) -> io::Result<()> {
+        match &self.args.delete_log {
+            Some(log) => log_deletion(log, path, size, action),
+            None => Ok(()),
+        }
+    }
+
+    /// Open the rename prompt, prefilled with the selected entry's name
+    pub fn start_rename(&mut self) {
+        if self.scan_running()
+            || self.refuse_if_read_only("renaming")
+            || self.refuse_if_from_manifest("rename")
+        {
+            return;
+        }
+        if let Some(node) = self.selected_node() {
+            self.prompt = Some(Prompt {
+                kind: PromptKind::Rename,
+                input: node.borrow().name.clone(),
+            });
+        }
+    }
+
+    /// Run the action of the open prompt with the text typed so far
+    pub fn submit_prompt(&mut self) {
+        let Some(prompt) = self.prompt.take() else {
+            return;
+        };
+        match prompt.kind {
+            PromptKind::Rename => self.rename_selected(prompt.input),
+            PromptKind::Move => self.move_marked(&prompt.input),
+            PromptKind::ExportDu => self.export_du(&prompt.input),
+        }
+    }
+
+    /// Close the prompt without running its action
+    pub fn cancel_prompt(&mut self) {
+        if let Some(prompt) = self.prompt.take() {
+            self.status_message = Some(
+                match prompt.kind {
+                    PromptKind::Rename => "Rename cancelled",
+                    PromptKind::Move => "Move cancelled",
+                    PromptKind::ExportDu => "Export cancelled",
+                }
+                .to_string(),
+            );
+        }
+    }
+
+    /// Rename the selected entry on disk to `new_name`
+    ///
+    /// On failure the tree is left untouched and the error is shown in the footer.
+    pub fn rename_selected(&mut self, new_name: String) {
+        if self.refuse_if_read_only("renaming") {
+            return;
+        }
+        let Some(node) = self.selected_node() else {
+            return;
+        };
+        // The prefilled name is lossy for non-UTF-8 names; leaving it untouched must not
+        // rename the entry to its mangled display name
+        if node.borrow().name == new_name {
+            return;
+        }
+        let old_path = node.borrow().path.clone();
+        let new_path = match rename_path(&old_path, &new_name) {
+            Ok(path) => path,
+            Err(e) => {
+                self.status_message = Some(format!("Rename failed: {}", e));
+                return;
+            }
+        };
+
+        {
+            let mut node = node.borrow_mut();
+            node.rebase(&old_path, &new_path);
+            node.name = new_name.clone();
+        }
+        let rebase = |path: &PathBuf| {
+            rebase_path(path, &old_path, &new_path).unwrap_or_else(|| path.clone())
+        };
+        self.marked = self.marked.iter().map(rebase).collect();
+        self.previous_sizes = self
+            .previous_sizes
+            .iter()
+            .map(|(path, size)| (rebase(path), *size))
+            .collect();
+        for path in self.bookmarks.values_mut() {
+            *path = rebase(path);
+        }
+
+        self.tree_stats = None;
+        self.sort_current_view();
+        self.status_message = Some(format!("Renamed to {}", new_name));
+    }
+
+    /// Entries a move applies to: the marked ones, or the selected one when nothing is marked
+    pub fn move_sources(&self) -> Vec<PathBuf> {
+        if self.marked.is_empty() {
+            self.selected_node()
+                .map(|n| vec![n.borrow().path.clone()])
+                .unwrap_or_default()
+        } else {
+            let mut sources: Vec<PathBuf> = self.marked.iter().cloned().collect();
+            sources.sort();
+            sources
+        }
+    }
+
+    /// Open the move prompt for the marked entries (or the selected one)
+    pub fn start_move(&mut self) {
+        if self.scan_running()
+            || self.refuse_if_read_only("moving")
+            || self.refuse_if_from_manifest("move")
+        {
+            return;
+        }
+        if self.move_sources().is_empty() {
+            return;
+        }
+        self.prompt = Some(Prompt {
+            kind: PromptKind::Move,
+            input: String::new(),
+        });
+    }
+
+    /// Move the marked entries (or the selected one) into `destination`
+    ///
+    /// Relative destinations are resolved against the current directory. Moved entries
+    /// are removed from their old place in the tree and, when the destination was
+    /// scanned too, added there.
+    pub fn move_marked(&mut self, destination: &str) {
+        if self.refuse_if_read_only("moving") {
+            return;
+        }
+        let dest_dir = self.current_path().join(destination.trim());
+        let current_path = self.current_path();
+        let dest_chain = self.find_scanned_dir(&dest_dir);
+
+        let mut moved = 0;
+        let mut first_error = None;
+        for source in self.move_sources() {
+            if current_path.starts_with(&source) {
+                first_error.get_or_insert_with(|| {
+                    format!("cannot move {}: it is being viewed", source.display())
+                });
+                continue;
+            }
+            let new_disk_path = match move_path(&source, &dest_dir) {
+                Ok(path) => path,
+                Err(e) => {
+                    first_error.get_or_insert_with(|| e.to_string());
+                    continue;
+                }
+            };
+            moved += 1;
+            self.marked.retain(|p| !p.starts_with(&source));
+
+            let Some(node) = find_path(&self.root, &source).and_then(|chain| detach(&chain)) else {
+                continue;
+            };
+            if let Some(chain) = &dest_chain
+                && let Some(dir) = chain.last()
+            {
+                let name = new_disk_path.file_name().unwrap_or_default();
+                let new_path = dir.borrow().path.join(name);
+                node.borrow_mut().rebase(&source, &new_path);
+                for path in self.bookmarks.values_mut() {
+                    if let Some(rebased) = rebase_path(path, &source, &new_path) {
+                        *path = rebased;
+                    }
+                }
+                attach(chain, node);
+            }
+        }
+
+        self.tree_stats = None;
+        self.recompute_marked_size();
+        self.sort_current_view();
+        let remaining = self.current_children().len();
+        match self.state.selected() {
+            _ if remaining == 0 => self.select(None),
+            Some(idx) if idx >= remaining => self.select(Some(remaining - 1)),
+            None => self.select(Some(0)),
+            Some(_) => {}
+        }
+        self.status_message = Some(match first_error {
+            None => format!("Moved {} item(s) to {}", moved, dest_dir.display()),
+            Some(e) => format!("Moved {} item(s); move failed: {}", moved, e),
+        });
+    }
+
+    /// Chain from the root to the scanned directory at `path` on disk, if it was scanned
+    fn find_scanned_dir(&self, path: &Path) -> Option<Vec<Rc<RefCell<FileNode>>>> {
+        let root_path = self.root.borrow().path.clone();
+        let relative = path
+            .canonicalize()
+            .ok()?
+            .strip_prefix(root_path.canonicalize().ok()?)
+            .ok()?
+            .to_path_buf();
+        let chain = find_path(&self.root, &root_path.join(relative))?;
+        chain
+            .last()
+            .is_some_and(|dir| dir.borrow().is_dir)
+            .then_some(chain)
+    }
+
+    /// Toggle the mark on the selected entry and move to the next one
+    pub fn toggle_mark(&mut self) {
+        let Some(node) = self.selected_node() else {
+            return;
+        };
+        let path = node.borrow().path.clone();
+        if !self.marked.remove(&path) {
+            self.marked.insert(path);
+        }
+        self.recompute_marked_size();
+        self.next();
+    }
+
+    /// Size change of an entry since the last refresh, or else since the `--compare` snapshot
+    pub fn size_delta(&self, path: &Path, size: u64) -> Option<i128> {
+        let baseline = if self.previous_sizes.is_empty() {
+            self.snapshot_sizes.as_ref()?
+        } else {
+            &self.previous_sizes
+        };
+        let previous = baseline.get(path).copied().unwrap_or(0);
+        let delta = size as i128 - previous as i128;
+        (delta != 0).then_some(delta)
+    }
+
+    /// Size change of the current directory since the `--compare` snapshot
+    ///
+    /// Unlike the rows, this also counts entries that are gone since the snapshot.
+    pub fn snapshot_delta(&self) -> Option<i128> {
+        let previous = self
+            .snapshot_sizes
+            .as_ref()?
+            .get(&self.current_path())
+            .copied()
+            .unwrap_or(0);
+        Some(self.current_total_size() as i128 - previous as i128)
+    }
+
+    pub fn is_marked(&self, path: &Path) -> bool {
+        self.marked.contains(path)
+    }
+
+    /// Recompute the combined size of marked entries, counting nested marks only once
+    fn recompute_marked_size(&mut self) {
+        fn sum_marked(node: &FileNode, marked: &HashSet<PathBuf>) -> u64 {
+            if marked.contains(&node.path) {
+                return node.size;
+            }
+            if !marked.iter().any(|p| p.starts_with(&node.path)) {
+                return 0;
+            }
+            node.children
+                .iter()
+                .map(|c| sum_marked(&c.borrow(), marked))
+                .sum()
+        }
+        self.marked_size = sum_marked(&self.root.borrow(), &self.marked);
+    }
+
+    /// Navigate to a directory by walking from the root along `path`, rebuilding the history
+    pub fn navigate_to(&mut self, path: &Path) -> bool {
+        let root_path = self.root.borrow().path.clone();
+        let Ok(relative) = path.strip_prefix(&root_path) else {
+            return false;
+        };
+
+        let mut history = Vec::new();
+        let mut node = Rc::clone(&self.root);
+        for component in relative.components() {
+            let next = node
+                .borrow()
+                .children
+                .iter()
+                .find(|c| {
+                    let c = c.borrow();
+                    c.is_dir && c.path.file_name() == Some(component.as_os_str())
+                })
+                .cloned();
+            match next {
+                Some(child) => {
+                    history.push(node);
+                    node = child;
+                }
+                None => return false,
+            }
+        }
+
+        self.path_history = history;
+        self.current_node = node;
+        self.trim_forward_history();
+        self.previous_sizes.clear();
+        *self.state.offset_mut() = 0;
+        self.sort_current_view();
+        if self.current_children().is_empty() {
+            self.select(None);
+        } else {
+            self.select(Some(0));
+        }
+        true
+    }
+
+    /// Open the view inside `subpath`, given relative to the scanned root
+    pub fn start_in(&mut self, subpath: &Path) -> Result<(), String> {
+        let root_path = self.root.borrow().path.clone();
+        if self.navigate_to(&root_path.join(subpath)) {
+            Ok(())
+        } else {
+            Err(format!(
+                "--start-in: '{}' is not a directory in the scan of '{}'",
+                subpath.display(),
+                root_path.display()
+            ))
+        }
+    }
+
+    /// Wait for a letter to bookmark the current directory under
+    pub fn start_set_bookmark(&mut self) {
+        self.pending_key = Some(PendingKey::SetBookmark);
+        self.status_message = Some("Set bookmark: press a letter".to_string());
+    }
+
+    /// Wait for a letter naming the bookmark to jump to
+    pub fn start_jump_to_bookmark(&mut self) {
+        self.pending_key = Some(PendingKey::JumpToBookmark);
+        self.status_message = Some("Jump to bookmark: press a letter".to_string());
+    }
+
+    pub fn set_bookmark(&mut self, key: char) {
+        let path = self.current_path();
+        self.status_message = Some(format!("Bookmark '{}' set: {}", key, path.display()));
+        self.bookmarks.insert(key, path);
+    }
+
+    pub fn jump_to_bookmark(&mut self, key: char) {
+        let Some(path) = self.bookmarks.get(&key).cloned() else {
+            self.status_message = Some(format!("No bookmark '{}'", key));
+            return;
+        };
+        self.status_message = Some(if self.navigate_to(&path) {
+            format!("Jumped to bookmark '{}'", key)
+        } else {
+            format!("Bookmark '{}' no longer exists in the tree", key)
+        });
     }
 }
@@ -1,5 +1,14 @@
+use crate::{
+    colors::{ColorChoice, HighlightMode, parse_color},
+    columns::ColumnKind,
+    exclude::parse_pattern,
+    file_node::SymlinkSize,
+    sort::{DirGrouping, SortMode},
+    utils::{TimeFormat, parse_size, parse_time},
+};
 use clap::Parser;
-use std::path::PathBuf;
+use ratatui::style::Color;
+use std::{path::PathBuf, time::SystemTime};
 
 /// RDU: A Rust-based Disk Usage analyzer for Windows
 #[derive(Parser, Debug, Clone)]
@@ -16,4 +25,261 @@ pub struct Args {
     /// Follow symbolic links and Junction points (Caution: can cause loops)
     #[arg(short = 'L', long)]
     pub follow_links: bool,
+
+    /// Follow symbolic links to directories only; linked files count as the link itself
+    #[arg(long, conflicts_with = "follow_links")]
+    pub follow_dir_links: bool,
+
+    /// What symbolic links that aren't followed count as: the link itself, the file it points to, or nothing
+    #[arg(long, value_enum, value_name = "MODE", default_value_t = SymlinkSize::Link)]
+    pub symlink_size: SymlinkSize,
+
+    /// Count allocated disk usage, including directory entries, instead of apparent file sizes
+    #[arg(long)]
+    pub disk_usage: bool,
+
+    /// Only count files with these comma-separated extensions (e.g. log,tmp)
+    #[arg(long, value_name = "LIST", value_delimiter = ',')]
+    pub ext: Vec<String>,
+
+    /// List only files with these comma-separated extensions, still counting every file
+    #[arg(long, value_name = "LIST", value_delimiter = ',')]
+    pub list_ext: Vec<String>,
+
+    /// Only count files modified at or after TIME, a date (2024-01-01, UTC) or an age (30d)
+    #[arg(long, value_name = "TIME", value_parser = parse_time)]
+    pub newer_than: Option<SystemTime>,
+
+    /// Only count files modified before TIME, a date (2024-01-01, UTC) or an age (1y)
+    #[arg(long, value_name = "TIME", value_parser = parse_time)]
+    pub older_than: Option<SystemTime>,
+
+    /// Leave out entries whose name or path below PATH matches GLOB (repeatable)
+    #[arg(long, value_name = "GLOB", value_parser = parse_pattern)]
+    pub exclude: Vec<String>,
+
+    /// Read more --exclude patterns from FILE, one per line (blank lines and # comments are skipped)
+    #[arg(long, value_name = "FILE")]
+    pub exclude_from: Option<PathBuf>,
+
+    /// Start in inode view, where sorting and bars use item counts instead of bytes
+    #[arg(long)]
+    pub inodes: bool,
+
+    /// Open the TUI inside this subdirectory of PATH instead of at the root
+    #[arg(long, value_name = "DIR")]
+    pub start_in: Option<PathBuf>,
+
+    /// Scan breadth-first for at most SECS and show the result as an estimate
+    ///
+    /// Directories not read in time are marked partial, and sizes above them are lower bounds.
+    #[arg(
+        long,
+        value_name = "SECS",
+        value_parser = clap::value_parser!(u64).range(1..),
+        conflicts_with_all = ["stdin", "from_manifest", "export_jsonl"]
+    )]
+    pub estimate: Option<u64>,
+
+    /// Open the TUI right away and fill in the tree while the scan runs
+    ///
+    /// Deleting, renaming, moving and refreshing wait until the scan is done.
+    #[arg(
+        long,
+        conflicts_with_all = ["stdin", "from_manifest", "estimate", "ext", "newer_than", "older_than", "start_in"]
+    )]
+    pub progressive: bool,
+
+    /// Time the scan of each top-level entry and list the slowest in the statistics overlay
+    #[arg(long, conflicts_with_all = ["stdin", "from_manifest"])]
+    pub scan_timing: bool,
+
+    /// Keep only directories in memory, adding up each directory's files in a single row
+    ///
+    /// Saves a node per file on huge trees; views of individual files have nothing to show.
+    #[arg(long, conflicts_with_all = ["stdin", "from_manifest"])]
+    pub dirs_only_tree: bool,
+
+    /// Read newline-separated paths from stdin instead of scanning PATH
+    #[arg(long, conflicts_with = "path")]
+    pub stdin: bool,
+
+    /// Load a `path<TAB>size<TAB>mtime` manifest instead of scanning the filesystem
+    #[arg(long, value_name = "FILE", conflicts_with_all = ["path", "stdin"])]
+    pub from_manifest: Option<PathBuf>,
+
+    /// Space to free, e.g. 10G: the footer shows progress and the directory total is drawn against it
+    #[arg(long, value_name = "SIZE", value_parser = parse_size)]
+    pub target: Option<u64>,
+
+    /// After SECS without a key press, rescan the current directory if its mtime changed
+    #[arg(long, value_name = "SECS", value_parser = clap::value_parser!(u64).range(1..))]
+    pub auto_refresh: Option<u64>,
+
+    /// Watch PATH and refresh the current directory when files below it change
+    #[arg(long, conflicts_with_all = ["stdin", "from_manifest"])]
+    pub watch: bool,
+
+    /// Delete permanently instead of moving entries to the trash
+    #[arg(long)]
+    pub no_trash: bool,
+
+    /// Disable deleting, renaming, moving and restoring from the trash, for inspection only
+    #[arg(long)]
+    pub read_only: bool,
+
+    /// Append every deletion (timestamp, path, size) to FILE
+    #[arg(long, value_name = "FILE")]
+    pub delete_log: Option<PathBuf>,
+
+    /// Go through the deletion prompts but only log to --delete-log instead of deleting
+    #[arg(long, requires = "delete_log")]
+    pub dry_run: bool,
+
+    /// Write the directory being viewed to FILE when quitting, for a shell function to cd to
+    #[arg(
+        long,
+        value_name = "FILE",
+        conflicts_with_all = ["export_jsonl", "output_tree", "summary", "bench"]
+    )]
+    pub choose_dir: Option<PathBuf>,
+
+    /// Print the path of the selected entry to stdout when quitting, drawing the TUI on stderr
+    #[arg(long, conflicts_with_all = ["export_jsonl", "output_tree", "summary", "bench"])]
+    pub print_selection: bool,
+
+    /// Draw inline instead of using the alternate screen, keeping the final view in the scrollback
+    #[arg(long)]
+    pub no_alt_screen: bool,
+
+    /// Group directories before or after files regardless of the sort mode
+    #[arg(long, value_enum, default_value_t = DirGrouping::Mixed)]
+    pub group_dirs: DirGrouping,
+
+    /// Sort directories by their own key and list them before files
+    #[arg(long, value_enum, value_name = "MODE")]
+    pub dir_sort: Option<SortMode>,
+
+    /// Sort files by their own key, listed after directories
+    #[arg(long, value_enum, value_name = "MODE")]
+    pub file_sort: Option<SortMode>,
+
+    /// Comma-separated list of columns in display order (size, disk, percent, bar, count, name)
+    #[arg(
+        long,
+        value_enum,
+        value_name = "LIST",
+        value_delimiter = ',',
+        default_value = "size,percent,bar,name"
+    )]
+    pub columns: Vec<ColumnKind>,
+
+    /// Decimal places shown in the percent column (0-2)
+    #[arg(long, value_name = "DIGITS", default_value_t = 1, value_parser = clap::value_parser!(u8).range(0..=2))]
+    pub percent_precision: u8,
+
+    /// Color sizes above SIZE yellow (0 disables)
+    #[arg(long, value_name = "SIZE", value_parser = parse_size, default_value = "0")]
+    pub warn_size: u64,
+
+    /// Color sizes above SIZE red (0 disables)
+    #[arg(long, value_name = "SIZE", value_parser = parse_size, default_value = "0")]
+    pub alert_size: u64,
+
+    /// Show at most N entries per directory, summarizing the rest in one row
+    #[arg(long, value_name = "N", value_parser = clap::value_parser!(u64).range(1..))]
+    pub max_items: Option<u64>,
+
+    /// How modification times are shown in the details and recent files views
+    #[arg(long, value_enum, value_name = "FORMAT", default_value_t = TimeFormat::Relative)]
+    pub time_format: TimeFormat,
+
+    /// Show the name first and the size, percent and bar columns on the right
+    #[arg(long)]
+    pub size_right: bool,
+
+    /// Draw bars and entry markers with ASCII characters only
+    #[arg(long)]
+    pub ascii: bool,
+
+    /// When to use colors; NO_COLOR in the environment turns off `auto`
+    #[arg(long, value_enum, value_name = "WHEN", default_value_t = ColorChoice::Auto)]
+    pub color: ColorChoice,
+
+    /// How the selected row is highlighted
+    #[arg(long, value_enum, default_value_t = HighlightMode::Color)]
+    pub highlight: HighlightMode,
+
+    /// Background color of the selected row (name, #rrggbb or 0-255)
+    #[arg(long, value_name = "COLOR", value_parser = parse_color)]
+    pub highlight_bg: Option<Color>,
+
+    /// Foreground color of the selected row (name, #rrggbb or 0-255)
+    #[arg(long, value_name = "COLOR", value_parser = parse_color)]
+    pub highlight_fg: Option<Color>,
+
+    /// Config file to read instead of the default location (e.g. ~/.config/rdu/config.toml)
+    #[arg(long, value_name = "FILE")]
+    pub config: Option<PathBuf>,
+
+    /// Scan, print machine-readable key=value timings and exit (for benchmarking)
+    #[arg(long, hide = true)]
+    pub bench: bool,
+
+    /// Print a table of PATH's entries with sizes to stdout instead of starting the TUI
+    ///
+    /// This is also what is printed when stdout isn't a terminal.
+    #[arg(long, conflicts_with = "output_tree")]
+    pub summary: bool,
+
+    /// Print the directory tree with sizes to stdout instead of starting the TUI
+    #[arg(long)]
+    pub output_tree: bool,
+
+    /// Stream every entry of PATH to FILE as JSON Lines while scanning ("-" for stdout)
+    #[arg(
+        long,
+        value_name = "FILE",
+        conflicts_with_all = ["stdin", "from_manifest", "output_tree", "summary"]
+    )]
+    pub export_jsonl: Option<PathBuf>,
+
+    /// Show how sizes changed since a snapshot written by --export-jsonl
+    ///
+    /// Paths are matched as written, so scan the same PATH the snapshot was taken of.
+    #[arg(
+        long,
+        value_name = "FILE",
+        conflicts_with_all = ["export_jsonl", "output_tree", "summary", "bench"]
+    )]
+    pub compare: Option<PathBuf>,
+
+    /// Write a treemap of PATH to FILE as SVG instead of starting the TUI ("-" for stdout)
+    #[arg(
+        long,
+        value_name = "FILE",
+        conflicts_with_all = ["export_jsonl", "output_tree", "summary", "bench", "compare", "choose_dir"]
+    )]
+    pub export_svg: Option<PathBuf>,
+
+    /// Print only the total size of PATH, like "12.4 GiB", and exit
+    ///
+    /// Filters such as --ext, --exclude and --newer-than apply to the total.
+    #[arg(
+        long,
+        conflicts_with_all = ["export_jsonl", "output_tree", "summary", "bench", "compare", "export_svg", "choose_dir"]
+    )]
+    pub oneline: bool,
+
+    /// Print the --oneline size in powers of 1000 (kB, MB, GB) instead of 1024
+    #[arg(long, requires = "oneline")]
+    pub si: bool,
+
+    /// Maximum depth printed by --output-tree
+    #[arg(long, value_name = "DEPTH")]
+    pub max_depth: Option<usize>,
+
+    /// Omit entries smaller than SIZE from the list, --output-tree and --summary (e.g. 10M, 1.5G)
+    #[arg(long, value_name = "SIZE", value_parser = parse_size, default_value = "0")]
+    pub min_size: u64,
 }
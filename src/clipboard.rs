@@ -0,0 +1,15 @@
+use crossterm::{clipboard::CopyToClipboard, execute};
+use std::io;
+
+/// Copy text to the system clipboard using the OSC 52 terminal escape sequence
+///
+/// The sequence goes to the terminal the TUI draws on: stderr with `--print-selection`,
+/// so it doesn't end up in the captured path, otherwise stdout.
+pub fn copy_to_clipboard(text: &str, print_selection: bool) -> io::Result<()> {
+    let command = CopyToClipboard::to_clipboard_from(text);
+    if print_selection {
+        execute!(io::stderr(), command)
+    } else {
+        execute!(io::stdout(), command)
+    }
+}
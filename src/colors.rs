@@ -1,4 +1,6 @@
-use ratatui::style::Color;
+use clap::ValueEnum;
+use ratatui::style::{Color, Modifier, Style};
+use std::str::FromStr;
 
 // Color Theme Constants
 pub const COLOR_HEADER_BG: Color = Color::Rgb(222, 222, 222); // Light gray background
@@ -8,8 +10,102 @@ pub const COLOR_SIZE: Color = Color::Rgb(78, 154, 6); // Green for sizes
 pub const COLOR_PERCENT: Color = Color::Rgb(255, 255, 255); // White for percentages
 pub const COLOR_DIRECTORY: Color = Color::Rgb(0, 220, 255); // Bright cyan for dirs
 pub const COLOR_FILE: Color = Color::Rgb(220, 220, 220); // Light gray for files
+pub const COLOR_GROWTH: Color = Color::Rgb(255, 95, 95); // Red for entries that grew
+pub const COLOR_SHRINK: Color = Color::Rgb(95, 215, 95); // Green for entries that shrank
+pub const COLOR_SIZE_WARN: Color = Color::Rgb(255, 215, 0); // Yellow for sizes above --warn-size
+pub const COLOR_SIZE_ALERT: Color = Color::Rgb(255, 60, 60); // Red for sizes above --alert-size
+pub const COLOR_COMPRESSED: Color = Color::Rgb(190, 130, 255); // Purple for compressed or sparse entries
+pub const COLOR_MOUNT_POINT: Color = Color::Rgb(255, 135, 215); // Pink for directories on another filesystem
+pub const COLOR_MARKED: Color = Color::Rgb(255, 165, 0); // Orange for marked entries
 pub const COLOR_HELP_TITLE: Color = Color::Rgb(0, 255, 255); // Bright cyan
 pub const COLOR_HELP_HEADER: Color = Color::Rgb(255, 220, 0); // Vibrant yellow
 pub const COLOR_HELP_HINT: Color = Color::Rgb(128, 128, 128); // Gray
 pub const COLOR_HIGHLIGHT_BG: Color = Color::Rgb(255, 255, 255); // White background when selected
 pub const COLOR_HIGHLIGHT_FG: Color = Color::Rgb(40, 40, 40); // Dark gray text when selected (matches terminal bg)
+
+/// How the selected row is highlighted
+#[derive(ValueEnum, Debug, Clone, Copy, PartialEq, Default)]
+pub enum HighlightMode {
+    /// Use the highlight background and foreground colors
+    #[default]
+    Color,
+    /// Swap the row's foreground and background (works with limited color support)
+    Reverse,
+    /// Render the row in bold without changing colors
+    Bold,
+}
+
+/// When to use colors (`--color`)
+#[derive(ValueEnum, Debug, Clone, Copy, PartialEq, Default)]
+pub enum ColorChoice {
+    /// Color output going to a terminal, unless NO_COLOR is set
+    #[default]
+    Auto,
+    /// Always color, also when piped and when NO_COLOR is set
+    Always,
+    /// Never color
+    Never,
+}
+
+impl ColorChoice {
+    /// Whether to color output, given whether it goes to a terminal
+    pub fn enabled(self, is_terminal: bool) -> bool {
+        match self {
+            ColorChoice::Always => true,
+            ColorChoice::Never => false,
+            // Any non-empty value counts, see https://no-color.org
+            ColorChoice::Auto => {
+                is_terminal && std::env::var_os("NO_COLOR").is_none_or(|v| v.is_empty())
+            }
+        }
+    }
+}
+
+/// Wrap `text` in ANSI escapes for a 24-bit `color` when `enabled`
+///
+/// Other colors are left plain; the theme constants are all 24-bit.
+pub fn paint(text: &str, color: Color, enabled: bool) -> String {
+    match color {
+        Color::Rgb(r, g, b) if enabled => format!("\x1b[38;2;{};{};{}m{}\x1b[0m", r, g, b, text),
+        _ => text.to_string(),
+    }
+}
+
+/// Runtime color settings that can be overridden from the command line
+#[derive(Debug, Clone)]
+pub struct Theme {
+    pub highlight_bg: Color,
+    pub highlight_fg: Color,
+    pub highlight_mode: HighlightMode,
+    /// Draw colors at all (`--color`); without them the highlight falls back to reverse video
+    pub colors: bool,
+}
+
+impl Default for Theme {
+    fn default() -> Self {
+        Self {
+            highlight_bg: COLOR_HIGHLIGHT_BG,
+            highlight_fg: COLOR_HIGHLIGHT_FG,
+            highlight_mode: HighlightMode::default(),
+            colors: true,
+        }
+    }
+}
+
+impl Theme {
+    pub fn highlight_style(&self) -> Style {
+        match self.highlight_mode {
+            HighlightMode::Color if !self.colors => {
+                Style::default().add_modifier(Modifier::REVERSED)
+            }
+            HighlightMode::Color => Style::default().bg(self.highlight_bg).fg(self.highlight_fg),
+            HighlightMode::Reverse => Style::default().add_modifier(Modifier::REVERSED),
+            HighlightMode::Bold => Style::default().add_modifier(Modifier::BOLD),
+        }
+    }
+}
+
+/// Parse a color name (`red`, `lightblue`), hex value (`#ff8800`) or 256-color index
+pub fn parse_color(input: &str) -> Result<Color, String> {
+    Color::from_str(input).map_err(|_| format!("invalid color '{}'", input))
+}
@@ -0,0 +1,68 @@
+use crate::sort::SortMode;
+use clap::ValueEnum;
+
+/// A column of the file list
+#[derive(ValueEnum, Debug, Clone, Copy, PartialEq)]
+pub enum ColumnKind {
+    /// Human-readable size
+    Size,
+    /// Space allocated on disk, shown when it differs noticeably from the file lengths
+    Disk,
+    /// Percentage of the directory total
+    Percent,
+    /// Percentage bar
+    Bar,
+    /// Number of direct children of a directory
+    Count,
+    /// Entry name (takes the remaining width)
+    Name,
+}
+
+impl ColumnKind {
+    /// Fixed width of the column, or `None` for the flexible name column
+    ///
+    /// The percent column fits `100%` plus the requested number of decimals.
+    pub fn width(&self, percent_precision: usize) -> Option<usize> {
+        match self {
+            ColumnKind::Size | ColumnKind::Disk => Some(10),
+            ColumnKind::Percent if percent_precision == 0 => Some(4),
+            ColumnKind::Percent => Some(5 + percent_precision),
+            ColumnKind::Bar => Some(10),
+            ColumnKind::Count => Some(8),
+            ColumnKind::Name => None,
+        }
+    }
+
+    /// Label shown in the list header
+    pub fn label(&self) -> &'static str {
+        match self {
+            ColumnKind::Size => "Size",
+            ColumnKind::Disk => "Disk",
+            ColumnKind::Percent => "%",
+            ColumnKind::Bar => "Usage",
+            ColumnKind::Count => "Items",
+            ColumnKind::Name => "Name",
+        }
+    }
+
+    /// Sort mode set by clicking the column's header
+    pub fn sort_mode(&self) -> SortMode {
+        match self {
+            ColumnKind::Size | ColumnKind::Disk | ColumnKind::Percent | ColumnKind::Bar => {
+                SortMode::Size
+            }
+            ColumnKind::Count => SortMode::ItemCount,
+            ColumnKind::Name => SortMode::Name,
+        }
+    }
+}
+
+/// Columns shown when `--columns` isn't given
+pub fn default_columns() -> Vec<ColumnKind> {
+    vec![
+        ColumnKind::Size,
+        ColumnKind::Percent,
+        ColumnKind::Bar,
+        ColumnKind::Name,
+    ]
+}
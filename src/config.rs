@@ -0,0 +1,68 @@
+use crate::{
+    exclude::parse_pattern,
+    keymap::{Action, KeySpecs},
+};
+use serde::Deserialize;
+use std::{
+    collections::HashMap,
+    fs, io,
+    path::{Path, PathBuf},
+};
+
+/// Settings read from the TOML config file
+///
+/// ```toml
+/// [keys]
+/// delete = "x"
+/// next = ["j", "Down", "Ctrl+n"]
+/// ```
+#[derive(Deserialize, Debug, Default)]
+#[serde(default, deny_unknown_fields)]
+pub struct Config {
+    /// Keys for each action, replacing that action's default keys
+    pub keys: HashMap<Action, KeySpecs>,
+}
+
+impl Config {
+    /// Where the config file is looked up when `--config` isn't given
+    pub fn default_path() -> Option<PathBuf> {
+        dirs::config_dir().map(|dir| dir.join("rdu").join("config.toml"))
+    }
+
+    /// Load `path`, or the default location when `None`
+    ///
+    /// A missing file at the default location gives the default settings; a missing
+    /// file given explicitly is an error.
+    pub fn load(path: Option<&Path>) -> Result<Self, String> {
+        let (path, required) = match path {
+            Some(path) => (path.to_path_buf(), true),
+            None => match Self::default_path() {
+                Some(path) => (path, false),
+                None => return Ok(Self::default()),
+            },
+        };
+        let text = match fs::read_to_string(&path) {
+            Ok(text) => text,
+            Err(e) if e.kind() == io::ErrorKind::NotFound && !required => {
+                return Ok(Self::default());
+            }
+            Err(e) => return Err(format!("{}: {}", path.display(), e)),
+        };
+        toml::from_str(&text).map_err(|e| format!("{}: {}", path.display(), e))
+    }
+}
+
+/// Read the glob patterns of an `--exclude-from` file, one per line
+///
+/// Surrounding whitespace is trimmed; blank lines and lines starting with `#` are skipped.
+pub fn read_exclude_file(path: &Path) -> Result<Vec<String>, String> {
+    let text = fs::read_to_string(path).map_err(|e| format!("{}: {}", path.display(), e))?;
+    text.lines()
+        .enumerate()
+        .map(|(i, line)| (i + 1, line.trim()))
+        .filter(|(_, line)| !line.is_empty() && !line.starts_with('#'))
+        .map(|(number, line)| {
+            parse_pattern(line).map_err(|e| format!("{}:{}: {}", path.display(), number, e))
+        })
+        .collect()
+}
@@ -0,0 +1,45 @@
+use globset::{Glob, GlobBuilder, GlobSet, GlobSetBuilder};
+use std::path::Path;
+
+/// Glob patterns for entries left out of scans (`--exclude` and `--exclude-from`)
+///
+/// A pattern matches an entry when it matches its name, or its path relative to the
+/// scanned root. `*` doesn't cross `/`, so `build/*` only covers direct children of a
+/// top-level `build` directory while `**/build` covers every `build` directory.
+#[derive(Debug, Clone, Default)]
+pub struct Excludes {
+    set: GlobSet,
+}
+
+impl Excludes {
+    pub fn new(patterns: &[String]) -> Result<Self, String> {
+        let mut builder = GlobSetBuilder::new();
+        for pattern in patterns {
+            builder.add(glob(pattern)?);
+        }
+        let set = builder.build().map_err(|e| e.to_string())?;
+        Ok(Self { set })
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.set.is_empty()
+    }
+
+    /// Whether the entry at `path`, found below `root`, is excluded
+    pub fn matches(&self, path: &Path, root: &Path) -> bool {
+        path.file_name().is_some_and(|name| self.set.is_match(name))
+            || self.set.is_match(path.strip_prefix(root).unwrap_or(path))
+    }
+}
+
+fn glob(pattern: &str) -> Result<Glob, String> {
+    GlobBuilder::new(pattern)
+        .literal_separator(true)
+        .build()
+        .map_err(|e| format!("invalid exclude pattern '{}': {}", pattern, e))
+}
+
+/// Check an exclude pattern given on the command line
+pub fn parse_pattern(pattern: &str) -> Result<String, String> {
+    glob(pattern).map(|_| pattern.to_string())
+}
@@ -0,0 +1,82 @@
+use crate::scanner::ScannedEntry;
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::HashMap,
+    fs::File,
+    io::{self, BufRead, BufReader, Write},
+    path::{Path, PathBuf},
+    time::UNIX_EPOCH,
+};
+
+/// One line of a JSON Lines export
+#[derive(Serialize)]
+struct JsonlEntry<'a> {
+    path: &'a str,
+    size: u64,
+    is_dir: bool,
+    /// Seconds since the Unix epoch, like manifest mtimes
+    mtime: Option<u64>,
+}
+
+/// The fields of an export line needed to compare against it
+#[derive(Deserialize)]
+struct SnapshotEntry {
+    path: PathBuf,
+    size: u64,
+}
+
+/// Load a JSON Lines export as the size of every path in it, subtrees included
+pub fn load_snapshot(path: &Path) -> io::Result<HashMap<PathBuf, u64>> {
+    parse_snapshot(BufReader::new(File::open(path)?))
+}
+
+/// Sum the entries of a JSON Lines export into the size of each path and its ancestors
+///
+/// The export doesn't list the scanned root itself, so its total is the sum of
+/// everything below it. Blank lines are skipped.
+pub fn parse_snapshot<R: BufRead>(reader: R) -> io::Result<HashMap<PathBuf, u64>> {
+    let mut sizes: HashMap<PathBuf, u64> = HashMap::new();
+    for (line_no, line) in reader.lines().enumerate() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        let entry: SnapshotEntry = serde_json::from_str(&line).map_err(|e| {
+            io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("snapshot line {}: {}", line_no + 1, e),
+            )
+        })?;
+        for path in entry.path.ancestors() {
+            *sizes.entry(path.to_path_buf()).or_default() += entry.size;
+        }
+    }
+    Ok(sizes)
+}
+
+/// Write one JSON object per entry as it arrives, returning the number of lines written
+///
+/// Nothing is kept in memory between entries, so this works on trees too large to
+/// build. Paths that aren't valid UTF-8 are written lossily.
+pub fn write_jsonl<W: Write>(
+    entries: impl Iterator<Item = ScannedEntry>,
+    writer: &mut W,
+) -> io::Result<u64> {
+    let mut count = 0;
+    for entry in entries {
+        let line = JsonlEntry {
+            path: &entry.path.to_string_lossy(),
+            size: entry.size,
+            is_dir: entry.is_dir,
+            mtime: entry
+                .modified_time
+                .and_then(|t| t.duration_since(UNIX_EPOCH).ok())
+                .map(|d| d.as_secs()),
+        };
+        serde_json::to_writer(&mut *writer, &line)?;
+        writer.write_all(b"\n")?;
+        count += 1;
+    }
+    writer.flush()?;
+    Ok(count)
+}
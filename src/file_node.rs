@@ -1,15 +1,112 @@
-use std::{cell::RefCell, path::PathBuf, rc::Rc, time::SystemTime};
+use crate::utils::rebase_path;
+use clap::ValueEnum;
+use std::{
+    cell::RefCell,
+    collections::HashSet,
+    path::{Path, PathBuf},
+    rc::Rc,
+    sync::atomic::{AtomicU64, Ordering},
+    time::{Duration, SystemTime},
+};
+
+/// The byte totals a node keeps for its subtree
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct ByteTotals {
+    pub size: u64,
+    pub apparent_size: u64,
+    pub disk_size: u64,
+}
+
+/// What a symbolic link that isn't followed counts as (`--symlink-size`)
+#[derive(ValueEnum, Debug, Clone, Copy, PartialEq, Default)]
+pub enum SymlinkSize {
+    /// The link itself: the length of the path it holds, or the space allocated for it
+    #[default]
+    Link,
+    /// The file it points to; links to directories and broken links count nothing
+    Target,
+    /// Nothing
+    Zero,
+}
+
+impl SymlinkSize {
+    pub fn next(self) -> Self {
+        match self {
+            SymlinkSize::Link => SymlinkSize::Target,
+            SymlinkSize::Target => SymlinkSize::Zero,
+            SymlinkSize::Zero => SymlinkSize::Link,
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            SymlinkSize::Link => "link size",
+            SymlinkSize::Target => "target size",
+            SymlinkSize::Zero => "zero",
+        }
+    }
+}
+
+/// The totals a symbolic link can count as, kept so [`SymlinkSize`] can change after a scan
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct LinkTotals {
+    pub link: ByteTotals,
+    pub target: ByteTotals,
+}
+
+impl LinkTotals {
+    pub fn totals(&self, mode: SymlinkSize) -> ByteTotals {
+        match mode {
+            SymlinkSize::Link => self.link,
+            SymlinkSize::Target => self.target,
+            SymlinkSize::Zero => ByteTotals::default(),
+        }
+    }
+}
+
+/// Source of node ids, shared by every tree built in this process
+static NEXT_ID: AtomicU64 = AtomicU64::new(1);
 
 /// Represents a file or directory
+///
+/// Nodes compare equal when they share an `id`, so a node stays identifiable
+/// across re-sorts, renames and refreshes that reuse it.
 #[derive(Debug, Clone)]
 pub struct FileNode {
+    /// Unique id assigned when the node is created
+    pub id: u64,
+    /// Name for display only: non-UTF-8 bytes are replaced, so filesystem operations use `path`
     pub name: String,
+    /// Exact path on disk, including names that aren't valid UTF-8
     pub path: PathBuf,
+    /// Bytes counted for this subtree: apparent sizes, or allocated ones with `--disk-usage`
     pub size: u64,
+    /// Sum of the file lengths in this subtree
+    pub apparent_size: u64,
+    /// Space allocated on disk for this subtree, directory entries included
+    pub disk_size: u64,
     pub is_dir: bool,
     pub children: Vec<Rc<RefCell<FileNode>>>,
+    /// Entries in this subtree that couldn't be read, so the totals are missing them
     pub error_count: usize,
+    /// Of `error_count`, the entries that couldn't be read for lack of permission
+    pub denied_count: usize,
     pub modified_time: Option<SystemTime>,
+    /// Number of entries (inodes) in this subtree, including the node itself
+    pub item_count: u64,
+    /// This directory, or one below it, wasn't read by an estimate scan, so its size is a lower bound
+    pub partial: bool,
+    /// Directory on another filesystem than its parent
+    pub is_mount_point: bool,
+    /// Files directly in this directory that have no node of their own (`--dirs-only-tree`)
+    pub folded_files: u64,
+    /// Bytes counted for the folded files
+    pub folded_size: u64,
+    /// What this node can count as, for a symbolic link that wasn't followed
+    pub link_totals: Option<LinkTotals>,
+    /// Time the scan spent in each entry below this one, slowest first; only set on a
+    /// scanned root with `--scan-timing`
+    pub scan_timings: Vec<(PathBuf, Duration)>,
 }
 
 impl FileNode {
@@ -21,17 +118,258 @@ impl FileNode {
         mtime: Option<SystemTime>,
     ) -> Self {
         Self {
+            id: NEXT_ID.fetch_add(1, Ordering::Relaxed),
             name,
             path,
             size,
+            apparent_size: size,
+            disk_size: size,
             is_dir,
             children: vec![],
             error_count: 0,
+            denied_count: 0,
             modified_time: mtime,
+            item_count: 1,
+            partial: false,
+            is_mount_point: false,
+            folded_files: 0,
+            folded_size: 0,
+            link_totals: None,
+            scan_timings: Vec::new(),
         }
     }
 
     pub fn child_count(&self) -> usize {
         self.children.len()
     }
+
+    /// The value used for sorting and bars: item count in inode view, bytes otherwise
+    pub fn metric(&self, inodes: bool) -> u64 {
+        if inodes { self.item_count } else { self.size }
+    }
+
+    /// [`FileNode::metric`] of the files folded into this directory
+    pub fn folded_metric(&self, inodes: bool) -> u64 {
+        if inodes {
+            self.folded_files
+        } else {
+            self.folded_size
+        }
+    }
+
+    pub fn totals(&self) -> ByteTotals {
+        ByteTotals {
+            size: self.size,
+            apparent_size: self.apparent_size,
+            disk_size: self.disk_size,
+        }
+    }
+
+    /// Swap the share `old` of this node's totals for `new`, e.g. when a descendant changed
+    pub fn replace_totals(&mut self, old: ByteTotals, new: ByteTotals) {
+        self.size = (self.size + new.size).saturating_sub(old.size);
+        self.apparent_size =
+            (self.apparent_size + new.apparent_size).saturating_sub(old.apparent_size);
+        self.disk_size = (self.disk_size + new.disk_size).saturating_sub(old.disk_size);
+    }
+
+    /// Add a child's totals, as done while building a tree
+    pub fn add_totals(&mut self, totals: ByteTotals) {
+        self.replace_totals(ByteTotals::default(), totals);
+    }
+
+    /// Rewrite the paths of this node and its descendants after `old_prefix` was renamed
+    pub fn rebase(&mut self, old_prefix: &Path, new_prefix: &Path) {
+        if let Some(path) = rebase_path(&self.path, old_prefix, new_prefix) {
+            self.path = path;
+        }
+        for child in &self.children {
+            child.borrow_mut().rebase(old_prefix, new_prefix);
+        }
+    }
+}
+
+impl PartialEq for FileNode {
+    fn eq(&self, other: &Self) -> bool {
+        self.id == other.id
+    }
+}
+
+impl Eq for FileNode {}
+
+/// Find the node with the given id in the subtree
+pub fn find_by_id(node: &Rc<RefCell<FileNode>>, id: u64) -> Option<Rc<RefCell<FileNode>>> {
+    if node.borrow().id == id {
+        return Some(Rc::clone(node));
+    }
+    node.borrow()
+        .children
+        .iter()
+        .find_map(|child| find_by_id(child, id))
+}
+
+/// Directories below `node` reached by following directories with a single child
+///
+/// Used to show chains like `a/b/c` as one row; empty unless `node`'s only child is a directory.
+pub fn single_child_chain(node: &Rc<RefCell<FileNode>>) -> Vec<Rc<RefCell<FileNode>>> {
+    let mut chain = Vec::new();
+    let mut current = Rc::clone(node);
+    loop {
+        let next = match current.borrow().children.as_slice() {
+            [only] if only.borrow().is_dir => Rc::clone(only),
+            _ => return chain,
+        };
+        chain.push(Rc::clone(&next));
+        current = next;
+    }
+}
+
+/// Nodes from `root` down to the node at `path`, both included
+///
+/// `path` must use the same form as the tree's paths (e.g. relative to the same base).
+pub fn find_path(root: &Rc<RefCell<FileNode>>, path: &Path) -> Option<Vec<Rc<RefCell<FileNode>>>> {
+    let relative = path.strip_prefix(&root.borrow().path).ok()?;
+    let mut chain = vec![Rc::clone(root)];
+    for component in relative.components() {
+        let next = chain
+            .last()?
+            .borrow()
+            .children
+            .iter()
+            .find(|c| c.borrow().path.file_name() == Some(component.as_os_str()))
+            .cloned()?;
+        chain.push(next);
+    }
+    Some(chain)
+}
+
+/// Remove the last node of `chain` from its parent, updating the totals of every ancestor
+pub fn detach(chain: &[Rc<RefCell<FileNode>>]) -> Option<Rc<RefCell<FileNode>>> {
+    let (node, ancestors) = chain.split_last()?;
+    let parent = ancestors.last()?;
+    parent
+        .borrow_mut()
+        .children
+        .retain(|c| !Rc::ptr_eq(c, node));
+    let (totals, count, errors, denied) = {
+        let node = node.borrow();
+        (
+            node.totals(),
+            node.item_count,
+            node.error_count,
+            node.denied_count,
+        )
+    };
+    for ancestor in ancestors {
+        let mut ancestor = ancestor.borrow_mut();
+        ancestor.replace_totals(totals, ByteTotals::default());
+        ancestor.item_count = ancestor.item_count.saturating_sub(count);
+        ancestor.error_count = ancestor.error_count.saturating_sub(errors);
+        ancestor.denied_count = ancestor.denied_count.saturating_sub(denied);
+    }
+    Some(Rc::clone(node))
+}
+
+/// Add `node` as a child of the last node of `chain`, updating the totals of every ancestor
+pub fn attach(chain: &[Rc<RefCell<FileNode>>], node: Rc<RefCell<FileNode>>) {
+    let Some(parent) = chain.last() else {
+        return;
+    };
+    let (totals, count, errors, denied) = {
+        let node = node.borrow();
+        (
+            node.totals(),
+            node.item_count,
+            node.error_count,
+            node.denied_count,
+        )
+    };
+    parent.borrow_mut().children.push(node);
+    for ancestor in chain {
+        let mut ancestor = ancestor.borrow_mut();
+        ancestor.add_totals(totals);
+        ancestor.item_count += count;
+        ancestor.error_count += errors;
+        ancestor.denied_count += denied;
+    }
+}
+
+/// Recompute `item_count` for every node in the subtree, returning the root's count
+///
+/// Folded files count as items of their directory.
+pub fn update_item_counts(node: &Rc<RefCell<FileNode>>) -> u64 {
+    let children: u64 = node.borrow().children.iter().map(update_item_counts).sum();
+    let mut node = node.borrow_mut();
+    node.item_count = 1 + node.folded_files + children;
+    node.item_count
+}
+
+/// Make every symbolic link below `node` count as `mode`, updating the totals above each
+pub fn apply_symlink_size(node: &Rc<RefCell<FileNode>>, mode: SymlinkSize) {
+    fn visit(
+        node: &Rc<RefCell<FileNode>>,
+        mode: SymlinkSize,
+        ancestors: &mut Vec<Rc<RefCell<FileNode>>>,
+    ) {
+        let link_totals = node.borrow().link_totals;
+        if let Some(link_totals) = link_totals {
+            let old = node.borrow().totals();
+            let new = link_totals.totals(mode);
+            node.borrow_mut().replace_totals(old, new);
+            for ancestor in ancestors.iter() {
+                ancestor.borrow_mut().replace_totals(old, new);
+            }
+            return;
+        }
+        ancestors.push(Rc::clone(node));
+        let children = node.borrow().children.clone();
+        for child in &children {
+            visit(child, mode, ancestors);
+        }
+        ancestors.pop();
+    }
+
+    visit(node, mode, &mut Vec::new());
+}
+
+/// Recompute which directories are partial, marking those in `unread` as partial too
+///
+/// A directory an estimate scan didn't read has no children and keeps its mark; any
+/// other directory is partial when one of its children is.
+pub fn update_partial(node: &Rc<RefCell<FileNode>>, unread: &HashSet<PathBuf>) -> bool {
+    // Every child has to be visited, so no short-circuiting `any`
+    let mut children_partial = false;
+    for child in &node.borrow().children {
+        children_partial |= update_partial(child, unread);
+    }
+    let mut node = node.borrow_mut();
+    let unread_here = node.children.is_empty() && node.partial;
+    node.partial = children_partial || unread_here || unread.contains(&node.path);
+    node.partial
+}
+
+/// Check that every directory's size equals the sum of its children's sizes, folded
+/// files included
+///
+/// Returns a description of the first mismatch found in a depth-first walk.
+pub fn verify_sizes(node: &FileNode) -> Result<(), String> {
+    if !node.is_dir {
+        return Ok(());
+    }
+
+    let children_sum: u64 =
+        node.folded_size + node.children.iter().map(|c| c.borrow().size).sum::<u64>();
+    if children_sum != node.size {
+        return Err(format!(
+            "{}: size {} != children sum {}",
+            node.path.display(),
+            node.size,
+            children_sum
+        ));
+    }
+
+    for child in &node.children {
+        verify_sizes(&child.borrow())?;
+    }
+    Ok(())
 }
@@ -0,0 +1,208 @@
+use std::{
+    fs::{self, OpenOptions},
+    io::{self, Write},
+    path::{MAIN_SEPARATOR, Path, PathBuf},
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+/// How an entry was removed from disk
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum DeleteMethod {
+    Trash,
+    Permanent,
+}
+
+impl DeleteMethod {
+    pub fn description(&self) -> &'static str {
+        match self {
+            DeleteMethod::Trash => "Moved to trash",
+            DeleteMethod::Permanent => "Permanently deleted",
+        }
+    }
+
+    /// Action recorded in the deletion log
+    pub fn log_name(&self) -> &'static str {
+        match self {
+            DeleteMethod::Trash => "trash",
+            DeleteMethod::Permanent => "permanent",
+        }
+    }
+}
+
+/// Delete a file or directory, either via the OS recycle bin or permanently
+pub fn delete_path(path: &Path, is_dir: bool, method: DeleteMethod) -> io::Result<()> {
+    match method {
+        DeleteMethod::Trash => trash::delete(path).map_err(io::Error::other),
+        DeleteMethod::Permanent if is_dir => fs::remove_dir_all(path),
+        DeleteMethod::Permanent => fs::remove_file(path),
+    }
+}
+
+/// Absolute form of `path` with its parent resolved, like the trash records it
+///
+/// The last component is left alone so a symlink maps to itself, not its target.
+pub fn absolute_path(path: &Path) -> PathBuf {
+    let absolute = std::path::absolute(path).unwrap_or_else(|_| path.to_path_buf());
+    match (absolute.parent(), absolute.file_name()) {
+        (Some(parent), Some(name)) => parent
+            .canonicalize()
+            .map_or_else(|_| absolute.clone(), |parent| parent.join(name)),
+        _ => absolute,
+    }
+}
+
+/// Put the most recently trashed entry that came from `original_path` back in place
+///
+/// Only supported where the `trash` crate can list the recycle bin (Windows and
+/// freedesktop systems); elsewhere this returns [`io::ErrorKind::Unsupported`].
+pub fn restore_from_trash(original_path: &Path) -> io::Result<()> {
+    #[cfg(any(
+        target_os = "windows",
+        all(
+            unix,
+            not(target_os = "macos"),
+            not(target_os = "ios"),
+            not(target_os = "android")
+        )
+    ))]
+    {
+        use trash::os_limited;
+
+        let item = os_limited::list()
+            .map_err(io::Error::other)?
+            .into_iter()
+            .filter(|item| item.original_path() == original_path)
+            .max_by_key(|item| item.time_deleted)
+            .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "not found in trash"))?;
+        os_limited::restore_all([item]).map_err(io::Error::other)
+    }
+
+    #[cfg(not(any(
+        target_os = "windows",
+        all(
+            unix,
+            not(target_os = "macos"),
+            not(target_os = "ios"),
+            not(target_os = "android")
+        )
+    )))]
+    {
+        let _ = original_path;
+        Err(io::Error::new(
+            io::ErrorKind::Unsupported,
+            "restoring from the trash is not supported on this platform",
+        ))
+    }
+}
+
+/// Append a `timestamp<TAB>path<TAB>size<TAB>action` line to the deletion log
+///
+/// The timestamp is in seconds since the Unix epoch, like manifest mtimes.
+pub fn log_deletion(log: &Path, path: &Path, size: u64, action: &str) -> io::Result<()> {
+    let timestamp = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |d| d.as_secs());
+    let mut file = OpenOptions::new().create(true).append(true).open(log)?;
+    writeln!(
+        file,
+        "{}\t{}\t{}\t{}",
+        timestamp,
+        path.display(),
+        size,
+        action
+    )
+}
+
+/// Rename an entry within its parent directory, returning the new path
+///
+/// Names containing path separators and names of existing entries are rejected
+/// before anything on disk is touched.
+pub fn rename_path(path: &Path, new_name: &str) -> io::Result<PathBuf> {
+    if new_name.is_empty()
+        || new_name == "."
+        || new_name == ".."
+        || new_name.contains(['/', MAIN_SEPARATOR, '\0'])
+    {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("invalid name '{}'", new_name),
+        ));
+    }
+
+    let new_path = path.with_file_name(new_name);
+    if new_path == path {
+        return Ok(new_path);
+    }
+    if fs::symlink_metadata(&new_path).is_ok() {
+        return Err(io::Error::new(
+            io::ErrorKind::AlreadyExists,
+            format!("'{}' already exists", new_name),
+        ));
+    }
+    fs::rename(path, &new_path)?;
+    Ok(new_path)
+}
+
+/// Move an entry into `dest_dir`, returning its new path
+///
+/// Falls back to copying and removing the original when a rename can't cross filesystems.
+pub fn move_path(path: &Path, dest_dir: &Path) -> io::Result<PathBuf> {
+    let name = path.file_name().ok_or_else(|| {
+        io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("cannot move {}", path.display()),
+        )
+    })?;
+    if !dest_dir.is_dir() {
+        return Err(io::Error::new(
+            io::ErrorKind::NotFound,
+            format!("{} is not a directory", dest_dir.display()),
+        ));
+    }
+    if dest_dir.canonicalize()?.starts_with(path.canonicalize()?) {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("cannot move {} into itself", path.display()),
+        ));
+    }
+
+    let target = dest_dir.join(name);
+    if fs::symlink_metadata(&target).is_ok() {
+        return Err(io::Error::new(
+            io::ErrorKind::AlreadyExists,
+            format!("{} already exists", target.display()),
+        ));
+    }
+    match fs::rename(path, &target) {
+        Ok(()) => Ok(target),
+        Err(e) if e.kind() == io::ErrorKind::CrossesDevices => {
+            copy_recursive(path, &target)?;
+            if fs::symlink_metadata(path)?.is_dir() {
+                fs::remove_dir_all(path)?;
+            } else {
+                fs::remove_file(path)?;
+            }
+            Ok(target)
+        }
+        Err(e) => Err(e),
+    }
+}
+
+/// Copy a file, symlink or directory tree to `to`
+fn copy_recursive(from: &Path, to: &Path) -> io::Result<()> {
+    let meta = fs::symlink_metadata(from)?;
+    if meta.is_dir() {
+        fs::create_dir(to)?;
+        for entry in fs::read_dir(from)? {
+            let entry = entry?;
+            copy_recursive(&entry.path(), &to.join(entry.file_name()))?;
+        }
+        Ok(())
+    } else {
+        #[cfg(unix)]
+        if meta.file_type().is_symlink() {
+            return std::os::unix::fs::symlink(fs::read_link(from)?, to);
+        }
+        fs::copy(from, to).map(|_| ())
+    }
+}
@@ -0,0 +1,241 @@
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use serde::Deserialize;
+use std::collections::HashMap;
+
+/// Something a key press in the file list can do
+#[derive(Deserialize, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[serde(rename_all = "kebab-case")]
+pub enum Action {
+    Quit,
+    Help,
+    // Navigation
+    Next,
+    Previous,
+    PageDown,
+    PageUp,
+    First,
+    Last,
+    // Actions
+    Enter,
+    Up,
+    Forward,
+    Refresh,
+    ForceRefresh,
+    CopyPath,
+    CopySize,
+    ExportDu,
+    Delete,
+    UndoTrash,
+    ExcludeEntry,
+    ClearExclusions,
+    Rename,
+    Move,
+    Mark,
+    SetBookmark,
+    JumpToBookmark,
+    // Sorting
+    SortSize,
+    SortMtime,
+    SortCount,
+    CycleSort,
+    CycleGrouping,
+    ResetView,
+    // Display
+    BarScale,
+    PercentBasis,
+    InodeView,
+    DirsOnly,
+    InvertFilter,
+    Cumulative,
+    RootBar,
+    BarColumn,
+    CollapseChains,
+    CycleSymlinkSize,
+    RecentFiles,
+    Histogram,
+    Details,
+    Peek,
+    Stats,
+    Verify,
+}
+
+/// Keys bound to each action when the config file doesn't rebind it
+const DEFAULT_BINDINGS: &[(Action, &[&str])] = &[
+    (Action::Quit, &["q", "Esc"]),
+    (Action::Help, &["?"]),
+    (Action::Next, &["j", "Down"]),
+    (Action::Previous, &["k", "Up"]),
+    (Action::PageDown, &["Ctrl+d", "PageDown"]),
+    (Action::PageUp, &["Ctrl+u", "PageUp"]),
+    (Action::First, &["H", "Home"]),
+    (Action::Last, &["G", "End"]),
+    (Action::Enter, &["o", "l", "Enter", "Right"]),
+    (Action::Up, &["u", "h", "Backspace", "Left"]),
+    (Action::Forward, &["f"]),
+    (Action::Refresh, &["r"]),
+    (Action::ForceRefresh, &["Ctrl+r"]),
+    (Action::CopyPath, &["y"]),
+    (Action::CopySize, &["Y"]),
+    (Action::ExportDu, &["E"]),
+    (Action::Delete, &["d"]),
+    (Action::UndoTrash, &["U"]),
+    (Action::ExcludeEntry, &["x"]),
+    (Action::ClearExclusions, &["X"]),
+    (Action::Rename, &["R"]),
+    (Action::Move, &["M"]),
+    (Action::Mark, &["Space"]),
+    (Action::SetBookmark, &["B"]),
+    (Action::JumpToBookmark, &["'"]),
+    (Action::SortSize, &["s"]),
+    (Action::SortMtime, &["m"]),
+    (Action::SortCount, &["c"]),
+    (Action::CycleSort, &["Tab"]),
+    (Action::CycleGrouping, &["g"]),
+    (Action::ResetView, &["="]),
+    (Action::BarScale, &["v"]),
+    (Action::PercentBasis, &["%"]),
+    (Action::InodeView, &["#"]),
+    (Action::DirsOnly, &["D"]),
+    (Action::InvertFilter, &["!"]),
+    (Action::Cumulative, &["+"]),
+    (Action::RootBar, &["p"]),
+    (Action::BarColumn, &["b"]),
+    (Action::CollapseChains, &["C"]),
+    (Action::CycleSymlinkSize, &["L"]),
+    (Action::RecentFiles, &["t"]),
+    (Action::Histogram, &["z"]),
+    (Action::Details, &["i"]),
+    (Action::Peek, &["e"]),
+    (Action::Stats, &["S"]),
+    (Action::Verify, &["V"]),
+];
+
+/// A key together with the Ctrl and Alt modifiers held with it
+///
+/// Shift isn't part of the combo: it already shows in the character (`G` rather
+/// than `g`), and terminals disagree on whether they report it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct KeyCombo {
+    pub code: KeyCode,
+    pub modifiers: KeyModifiers,
+}
+
+impl KeyCombo {
+    fn new(code: KeyCode, modifiers: KeyModifiers) -> Self {
+        Self {
+            code,
+            modifiers: modifiers & (KeyModifiers::CONTROL | KeyModifiers::ALT),
+        }
+    }
+
+    /// Parse a key spec like `j`, `G`, `Ctrl+d`, `Alt+Enter`, `PageDown` or `F5`
+    ///
+    /// Named keys are case-insensitive; single characters are taken literally.
+    pub fn parse(spec: &str) -> Result<Self, String> {
+        let invalid = || format!("invalid key '{}'", spec);
+        let mut modifiers = KeyModifiers::NONE;
+        let mut key = spec;
+        // A lone "+" is the plus key, not an empty combo
+        while key.len() > 1
+            && let Some((modifier, rest)) = key.split_once('+')
+        {
+            modifiers |= match modifier.to_ascii_lowercase().as_str() {
+                "ctrl" | "control" => KeyModifiers::CONTROL,
+                "alt" => KeyModifiers::ALT,
+                "shift" => KeyModifiers::SHIFT,
+                _ => return Err(invalid()),
+            };
+            key = rest;
+        }
+
+        let mut chars = key.chars();
+        let code = match (chars.next(), chars.next()) {
+            (Some(c), None) if modifiers.contains(KeyModifiers::SHIFT) => {
+                KeyCode::Char(c.to_ascii_uppercase())
+            }
+            (Some(c), None) => KeyCode::Char(c),
+            _ => match key.to_ascii_lowercase().as_str() {
+                "space" => KeyCode::Char(' '),
+                "enter" | "return" => KeyCode::Enter,
+                "esc" | "escape" => KeyCode::Esc,
+                "backspace" | "bksp" => KeyCode::Backspace,
+                "tab" if modifiers.contains(KeyModifiers::SHIFT) => KeyCode::BackTab,
+                "tab" => KeyCode::Tab,
+                "backtab" => KeyCode::BackTab,
+                "up" => KeyCode::Up,
+                "down" => KeyCode::Down,
+                "left" => KeyCode::Left,
+                "right" => KeyCode::Right,
+                "pageup" | "pgup" => KeyCode::PageUp,
+                "pagedown" | "pgdn" => KeyCode::PageDown,
+                "home" => KeyCode::Home,
+                "end" => KeyCode::End,
+                "insert" | "ins" => KeyCode::Insert,
+                "delete" | "del" => KeyCode::Delete,
+                name => name
+                    .strip_prefix('f')
+                    .and_then(|n| n.parse().ok())
+                    .filter(|n| (1..=12).contains(n))
+                    .map(KeyCode::F)
+                    .ok_or_else(invalid)?,
+            },
+        };
+        Ok(Self::new(code, modifiers))
+    }
+}
+
+impl From<KeyEvent> for KeyCombo {
+    fn from(event: KeyEvent) -> Self {
+        Self::new(event.code, event.modifiers)
+    }
+}
+
+/// One key spec or a list of them, as written in the config file
+#[derive(Deserialize, Debug, Clone)]
+#[serde(untagged)]
+pub enum KeySpecs {
+    One(String),
+    Many(Vec<String>),
+}
+
+impl KeySpecs {
+    fn specs(&self) -> Vec<&str> {
+        match self {
+            KeySpecs::One(spec) => vec![spec.as_str()],
+            KeySpecs::Many(specs) => specs.iter().map(String::as_str).collect(),
+        }
+    }
+}
+
+/// Which action each key press in the file list triggers
+#[derive(Debug, Clone)]
+pub struct Keymap {
+    bindings: HashMap<KeyCombo, Action>,
+}
+
+impl Keymap {
+    /// Build the keymap from the defaults, replacing the keys of every action in `overrides`
+    ///
+    /// When a key is bound by the defaults and by an override, the override wins.
+    pub fn new(overrides: &HashMap<Action, KeySpecs>) -> Result<Self, String> {
+        let mut bindings = HashMap::new();
+        for (action, specs) in DEFAULT_BINDINGS {
+            if overrides.contains_key(action) {
+                continue;
+            }
+            for spec in specs.iter() {
+                bindings.insert(KeyCombo::parse(spec)?, *action);
+            }
+        }
+        for (action, specs) in overrides {
+            for spec in specs.specs() {
+                bindings.insert(KeyCombo::parse(spec)?, *action);
+            }
+        }
+        Ok(Self { bindings })
+    }
+
+    pub fn action(&self, key: KeyEvent) -> Option<Action> {
+        self.bindings.get(&KeyCombo::from(key)).copied()
+    }
+}
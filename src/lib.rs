@@ -1,14 +1,28 @@
 pub mod app;
 pub mod args;
+pub mod clipboard;
 pub mod colors;
+pub mod columns;
+pub mod config;
+pub mod exclude;
+pub mod export;
 pub mod file_node;
+pub mod fs_ops;
+pub mod keymap;
+pub mod manifest;
 pub mod scanner;
 pub mod sort;
+pub mod tree_output;
+pub mod treemap;
 pub mod ui;
 pub mod utils;
+pub mod watch;
 
-pub use app::App;
+pub use app::{App, TreeHook};
 pub use args::Args;
 pub use file_node::FileNode;
-pub use scanner::scan_dir;
-pub use sort::SortMode;
+pub use scanner::{
+    BackgroundScan, EstimateEntries, LiveScan, ScanCache, ScannedEntry, build_tree, scan_dir,
+    scan_dir_cached, scan_dir_estimate, scan_dir_streaming, scan_paths,
+};
+pub use sort::{DirGrouping, SortMode};
@@ -1,103 +1,584 @@
 use clap::Parser;
 use crossterm::{
+    cursor::Show,
     event::{
         self, DisableMouseCapture, EnableMouseCapture, Event, KeyCode, KeyEventKind, KeyModifiers,
+        MouseButton, MouseEvent, MouseEventKind,
     },
     execute,
-    terminal::{EnterAlternateScreen, LeaveAlternateScreen, disable_raw_mode, enable_raw_mode},
+    terminal::{
+        self, EnterAlternateScreen, LeaveAlternateScreen, disable_raw_mode, enable_raw_mode,
+    },
+};
+use ratatui::{Terminal, TerminalOptions, Viewport, backend::CrosstermBackend, layout::Position};
+use std::{
+    cell::RefCell,
+    collections::HashMap,
+    fs::{self, File},
+    io::{self, BufWriter, IsTerminal, Write},
+    path::{Path, PathBuf},
+    process,
+    rc::Rc,
+    time::{Duration, Instant},
+};
+
+use rdu::{
+    App, Args, BackgroundScan, FileNode, LiveScan, ScanCache,
+    app::{Overlay, PendingKey},
+    build_tree,
+    config::{Config, read_exclude_file},
+    export::{load_snapshot, write_jsonl},
+    keymap::{Action, Keymap},
+    manifest::load_manifest,
+    scan_dir, scan_dir_estimate, scan_dir_streaming, scan_paths,
+    tree_output::{write_summary, write_tree},
+    treemap::write_svg,
+    ui,
+    utils::{format_size, format_size_si, num_cpus, resolve_path},
+    watch::FsWatcher,
 };
-use ratatui::{Terminal, backend::CrosstermBackend};
-use std::io;
 
-use rdu::{App, Args, scan_dir, ui};
+/// The TUI's terminal, drawing to stdout or, with `--print-selection`, to stderr
+type Tui = Terminal<CrosstermBackend<Box<dyn Write>>>;
+
+/// Where the TUI draws, keeping stdout free for `--print-selection`
+///
+/// Buffered so a frame goes out in a few writes; stderr on its own writes every piece.
+/// The backend flushes after each draw.
+fn tui_output(print_selection: bool) -> Box<dyn Write> {
+    if print_selection {
+        Box::new(BufWriter::new(io::stderr()))
+    } else {
+        Box::new(BufWriter::new(io::stdout()))
+    }
+}
 
-fn run_app(terminal: &mut Terminal<CrosstermBackend<io::Stdout>>, mut app: App) -> io::Result<()> {
+/// Run the event loop until quit, returning the path of the entry selected at that point
+fn run_app(
+    terminal: &mut Tui,
+    mut app: App,
+    mut watcher: Option<FsWatcher>,
+    keymap: &Keymap,
+) -> io::Result<Option<PathBuf>> {
+    let idle_timeout = app.args.auto_refresh.map(Duration::from_secs);
+    let mut last_input = Instant::now();
     loop {
+        if app.live_scan.is_some() {
+            app.poll_live_scan();
+            if app.live_scan.is_none() {
+                // Scan warnings go to stderr and may have drawn over the screen
+                terminal.clear()?;
+            }
+        }
         terminal.draw(|f| ui::ui(f, &mut app))?;
 
-        if let Event::Key(key) = event::read()?
-            && key.kind == KeyEventKind::Press
+        // Wake up regularly while watching or scanning so changes show without a key press
+        let tick = match (
+            watcher.as_ref().map(FsWatcher::tick),
+            app.live_scan.is_some(),
+        ) {
+            (Some(tick), true) => Some(tick.min(SCAN_FRAME)),
+            (Some(tick), false) => Some(tick),
+            (None, true) => Some(SCAN_FRAME),
+            (None, false) => None,
+        };
+        let poll_timeout = match (tick, idle_timeout) {
+            (Some(tick), Some(idle)) => Some(tick.min(idle.saturating_sub(last_input.elapsed()))),
+            (Some(tick), None) => Some(tick),
+            (None, idle) => idle.map(|idle| idle.saturating_sub(last_input.elapsed())),
+        };
+        if let Some(timeout) = poll_timeout
+            && !event::poll(timeout)?
         {
-            // Clear status message on any key press
-            app.status_message = None;
-
-            match (key.code, key.modifiers) {
-                (KeyCode::Char('q'), _) | (KeyCode::Esc, _) if !app.show_help => return Ok(()),
-                (KeyCode::Esc, _) => app.show_help = false,
-                (KeyCode::Char('?'), _) => app.show_help = !app.show_help,
-                _ if app.show_help => app.show_help = false, // Any key closes help
-                // Navigation
-                (KeyCode::Down, _) | (KeyCode::Char('j'), _) => app.next(),
-                (KeyCode::Up, _) | (KeyCode::Char('k'), _) => app.previous(),
-                (KeyCode::Char('d'), KeyModifiers::CONTROL) | (KeyCode::PageDown, _) => {
-                    app.page_down()
+            if app.live_scan.is_none()
+                && let Some(watcher) = watcher.as_mut()
+                && watcher.changed_below(&app.current_path())
+            {
+                app.force_refresh();
+                app.status_message = Some("Refreshed: files changed".to_string());
+            }
+            if let Some(idle) = idle_timeout
+                && last_input.elapsed() >= idle
+            {
+                app.refresh_if_changed();
+                last_input = Instant::now();
+            }
+            continue;
+        }
+
+        let key = match event::read()? {
+            Event::Key(key) if key.kind == KeyEventKind::Press => key,
+            Event::Mouse(mouse) => {
+                handle_mouse(&mut app, mouse);
+                continue;
+            }
+            // Resizes need nothing either: the next draw reflows the list and overlays
+            _ => continue,
+        };
+        last_input = Instant::now();
+
+        // Clear status message on any key press
+        app.status_message = None;
+
+        if let Some(pending) = app.pending_key.take() {
+            if let KeyCode::Char(c) = key.code
+                && c.is_ascii_alphabetic()
+            {
+                match pending {
+                    PendingKey::SetBookmark => app.set_bookmark(c),
+                    PendingKey::JumpToBookmark => app.jump_to_bookmark(c),
                 }
-                (KeyCode::Char('u'), KeyModifiers::CONTROL) | (KeyCode::PageUp, _) => app.page_up(),
-                (KeyCode::Char('H'), _) | (KeyCode::Home, _) => app.go_to_first(),
-                (KeyCode::Char('G'), _) | (KeyCode::End, _) => app.go_to_last(),
-                // Actions
-                (KeyCode::Enter, _)
-                | (KeyCode::Right, _)
-                | (KeyCode::Char('l'), _)
-                | (KeyCode::Char('o'), _) => app.enter_dir(),
-                (KeyCode::Backspace, _)
-                | (KeyCode::Left, _)
-                | (KeyCode::Char('h'), _)
-                | (KeyCode::Char('u'), _) => app.go_up(),
-                (KeyCode::Char('r'), _) => app.refresh(),
-                // Sort options
-                (KeyCode::Char('s'), _) => app.toggle_sort_by_size(),
-                (KeyCode::Char('m'), _) => app.toggle_sort_by_mtime(),
-                (KeyCode::Char('c'), _) => app.toggle_sort_by_count(),
+            }
+            continue;
+        }
+
+        if let Some(prompt) = app.prompt.as_mut() {
+            match key.code {
+                KeyCode::Enter => app.submit_prompt(),
+                KeyCode::Esc => app.cancel_prompt(),
+                KeyCode::Backspace => {
+                    prompt.input.pop();
+                }
+                KeyCode::Char(c) => prompt.input.push(c),
                 _ => {}
             }
+            continue;
+        }
+
+        if app.confirm_delete {
+            match key.code {
+                KeyCode::Char('y') | KeyCode::Char('Y') => app.delete_selected(),
+                _ => {
+                    app.confirm_delete = false;
+                    app.status_message = Some("Delete cancelled".to_string());
+                }
+            }
+            continue;
+        }
+
+        if let Some(overlay) = app.overlay {
+            let page = i32::from(app.overlay_area.height.saturating_sub(2)).max(1);
+            match key.code {
+                KeyCode::Down | KeyCode::Char('j') => app.scroll_overlay(1),
+                KeyCode::Up | KeyCode::Char('k') => app.scroll_overlay(-1),
+                KeyCode::PageDown => app.scroll_overlay(page),
+                KeyCode::PageUp => app.scroll_overlay(-page),
+                KeyCode::Home => app.overlay_scroll = 0,
+                KeyCode::End => app.overlay_scroll = app.overlay_max_scroll,
+                KeyCode::Char('?') | KeyCode::Char('q') | KeyCode::Esc => app.overlay = None,
+                // Help only closes on the keys above; other overlays close on any key
+                _ if overlay == Overlay::Help => {}
+                _ => app.overlay = None,
+            }
+            continue;
+        }
+
+        match keymap.action(key) {
+            Some(Action::Quit) => {
+                if let Some(file) = &app.args.choose_dir {
+                    write_chosen_dir(file, &app.current_path())?;
+                }
+                return Ok(app.selected_node().map(|node| node.borrow().path.clone()));
+            }
+            Some(action) => dispatch(&mut app, action),
+            None => {}
         }
     }
 }
 
-fn setup_panic_hook() {
+/// Write the absolute form of `dir` to `file` for `--choose-dir`, without a trailing newline
+fn write_chosen_dir(file: &Path, dir: &Path) -> io::Result<()> {
+    let dir = std::path::absolute(dir)?;
+    fs::write(file, dir.as_os_str().as_encoded_bytes())
+}
+
+/// Run a file list action bound in the keymap
+fn dispatch(app: &mut App, action: Action) {
+    match action {
+        // Quitting ends the event loop, so run_app handles it
+        Action::Quit => {}
+        Action::Help => app.toggle_overlay(Overlay::Help),
+        // Navigation
+        Action::Next => app.next(),
+        Action::Previous => app.previous(),
+        Action::PageDown => app.page_down(),
+        Action::PageUp => app.page_up(),
+        Action::First => app.go_to_first(),
+        Action::Last => app.go_to_last(),
+        // Actions
+        Action::Enter => app.enter_dir(),
+        Action::Up => app.go_up(),
+        Action::Forward => app.go_forward(),
+        Action::Refresh => app.refresh(),
+        Action::ForceRefresh => app.force_refresh(),
+        Action::CopyPath => app.copy_selected_path(),
+        Action::CopySize => app.copy_selected_size(),
+        Action::ExportDu => app.start_export_du(),
+        Action::Delete => app.request_delete(),
+        Action::UndoTrash => app.undo_trash(),
+        Action::ExcludeEntry => app.exclude_selected(),
+        Action::ClearExclusions => app.clear_exclusions(),
+        Action::Rename => app.start_rename(),
+        Action::Move => app.start_move(),
+        Action::Mark => app.toggle_mark(),
+        Action::SetBookmark => app.start_set_bookmark(),
+        Action::JumpToBookmark => app.start_jump_to_bookmark(),
+        // Sort options
+        Action::SortSize => app.toggle_sort_by_size(),
+        Action::SortMtime => app.toggle_sort_by_mtime(),
+        Action::SortCount => app.toggle_sort_by_count(),
+        Action::CycleSort => app.cycle_sort(),
+        Action::CycleGrouping => app.toggle_dir_grouping(),
+        Action::ResetView => app.reset_view(),
+        // Display
+        Action::BarScale => app.toggle_bar_scale(),
+        Action::PercentBasis => app.toggle_percent_basis(),
+        Action::InodeView => app.toggle_inode_view(),
+        Action::DirsOnly => app.toggle_dirs_only(),
+        Action::InvertFilter => app.toggle_invert_filter(),
+        Action::Cumulative => app.toggle_cumulative(),
+        Action::RootBar => app.toggle_root_bar(),
+        Action::BarColumn => app.toggle_bar(),
+        Action::CollapseChains => app.toggle_collapse_chains(),
+        Action::CycleSymlinkSize => app.cycle_symlink_size(),
+        Action::RecentFiles => app.toggle_overlay(Overlay::RecentFiles),
+        Action::Histogram => app.toggle_overlay(Overlay::Histogram),
+        Action::Details => app.toggle_overlay(Overlay::Details),
+        Action::Peek => app.toggle_overlay(Overlay::Peek),
+        Action::Stats => app.toggle_overlay(Overlay::Stats),
+        Action::Verify => app.verify_tree(),
+    }
+}
+
+/// Sort by a column when its header is clicked; scroll the open overlay with the wheel
+/// and close it on a click outside of it
+fn handle_mouse(app: &mut App, mouse: MouseEvent) {
+    if app.overlay.is_none() {
+        if mouse.kind == MouseEventKind::Down(MouseButton::Left)
+            && let Some(column) = app.header_column_at(Position::new(mouse.column, mouse.row))
+        {
+            app.toggle_sort(column.sort_mode());
+        }
+        return;
+    }
+    match mouse.kind {
+        MouseEventKind::ScrollDown => app.scroll_overlay(1),
+        MouseEventKind::ScrollUp => app.scroll_overlay(-1),
+        MouseEventKind::Down(MouseButton::Left)
+            if !app
+                .overlay_area
+                .contains(Position::new(mouse.column, mouse.row)) =>
+        {
+            app.overlay = None
+        }
+        _ => {}
+    }
+}
+
+fn setup_panic_hook(alt_screen: bool, print_selection: bool) {
     let original_hook = std::panic::take_hook();
     std::panic::set_hook(Box::new(move |panic_info| {
         // Attempt to restore terminal state
         let _ = disable_raw_mode();
-        let _ = execute!(io::stdout(), LeaveAlternateScreen, DisableMouseCapture);
+        let mut output = tui_output(print_selection);
+        let _ = execute!(output, DisableMouseCapture, Show);
+        if alt_screen {
+            let _ = execute!(output, LeaveAlternateScreen);
+        }
         original_hook(panic_info);
     }));
 }
 
+/// Scan the target path, the paths listed on stdin with `--stdin`, or load a manifest
+fn scan(args: &Args) -> io::Result<Rc<RefCell<FileNode>>> {
+    if let Some(manifest) = &args.from_manifest {
+        load_manifest(manifest)
+    } else if args.stdin {
+        let paths = io::stdin()
+            .lines()
+            .collect::<io::Result<Vec<_>>>()?
+            .into_iter()
+            .filter(|line| !line.trim().is_empty())
+            .map(PathBuf::from);
+        Ok(scan_paths(paths, args))
+    } else if let Some(secs) = args.estimate {
+        Ok(scan_dir_estimate(
+            &args.path,
+            args,
+            Duration::from_secs(secs),
+        ))
+    } else {
+        Ok(scan_dir(&args.path, args))
+    }
+}
+
+/// How often the screen is redrawn while a scan runs
+const SCAN_FRAME: Duration = Duration::from_millis(100);
+
+/// Run the initial scan on a background thread, drawing its progress until it's done
+///
+/// Returns `None` when the scan is cancelled with `q`, `Esc` or `Ctrl+C`.
+fn scan_with_progress(
+    terminal: &mut Tui,
+    args: &Args,
+) -> io::Result<Option<Rc<RefCell<FileNode>>>> {
+    let start = Instant::now();
+    let mut scan = BackgroundScan::spawn(&args.path, args);
+    loop {
+        if let Some(root_node) = scan.poll() {
+            return Ok(Some(root_node));
+        }
+        terminal.draw(|f| {
+            ui::render_scanning(f, &args.path, scan.items(), start.elapsed(), args.ascii);
+            if !args.color.enabled(true) {
+                ui::strip_colors(f.buffer_mut());
+            }
+        })?;
+        if event::poll(SCAN_FRAME)?
+            && let Event::Key(key) = event::read()?
+            && key.kind == KeyEventKind::Press
+            && matches!(
+                (key.code, key.modifiers),
+                (KeyCode::Char('q'), _)
+                    | (KeyCode::Esc, _)
+                    | (KeyCode::Char('c'), KeyModifiers::CONTROL)
+            )
+        {
+            return Ok(None);
+        }
+    }
+}
+
+/// Scan unless the tree is already loaded, then run the TUI in the prepared terminal
+///
+/// Returns the path selected when quitting, or `None` if nothing was selected.
+fn run_tui(
+    terminal: &mut Tui,
+    args: Args,
+    root_node: Option<Rc<RefCell<FileNode>>>,
+    snapshot: Option<HashMap<PathBuf, u64>>,
+    keymap: &Keymap,
+) -> Result<Option<PathBuf>, Box<dyn std::error::Error>> {
+    let progressive = args.progressive;
+    let root_node = match root_node {
+        Some(root_node) => root_node,
+        // Start from the bare root; the scan fills it in while the TUI runs
+        None if progressive => build_tree(&args.path, Vec::new(), &args, &ScanCache::default()),
+        None => match scan_with_progress(terminal, &args)? {
+            Some(root_node) => root_node,
+            None => return Ok(None),
+        },
+    };
+    // Scan warnings go to stderr and may have drawn over the screen
+    terminal.clear()?;
+
+    let watcher = if args.watch {
+        Some(FsWatcher::new(&args.path)?)
+    } else {
+        None
+    };
+    let start_in = args.start_in.clone();
+
+    let mut app = App::new(root_node, args);
+    app.snapshot_sizes = snapshot;
+    if progressive {
+        app.live_scan = Some(LiveScan::spawn(&app.root, &app.args));
+    }
+    if let Some(subpath) = start_in {
+        app.start_in(&subpath)?;
+    }
+    Ok(run_app(terminal, app, watcher, keymap)?)
+}
+
+/// Check that the directory to scan exists and can be listed
+fn validate_root(path: &Path) -> Result<(), String> {
+    let describe = |e: io::Error| match e.kind() {
+        io::ErrorKind::NotFound => format!("{}: no such file or directory", path.display()),
+        io::ErrorKind::PermissionDenied => format!("{}: permission denied", path.display()),
+        _ => format!("{}: {}", path.display(), e),
+    };
+    let meta = fs::metadata(path).map_err(describe)?;
+    if !meta.is_dir() {
+        return Err(format!("{}: not a directory", path.display()));
+    }
+    fs::read_dir(path).map(drop).map_err(describe)
+}
+
 fn main() -> Result<(), Box<dyn std::error::Error>> {
-    let args = Args::parse();
+    let mut args = Args::parse();
 
-    println!(
-        "Scanning {}... This may take a moment.",
-        args.path.display()
-    );
+    if let Some(file) = &args.exclude_from {
+        match read_exclude_file(file) {
+            Ok(patterns) => args.exclude.extend(patterns),
+            Err(e) => {
+                eprintln!("Error: {}", e);
+                process::exit(1);
+            }
+        }
+    }
+
+    // A mistyped path would otherwise scan to an empty tree
+    if !args.stdin
+        && args.from_manifest.is_none()
+        && let Err(e) = validate_root(&args.path)
+    {
+        eprintln!("Error: {}", e);
+        process::exit(1);
+    }
+    // `rdu .` or `rdu ../x/` would otherwise leave the root without a proper name
+    args.path = resolve_path(&args.path, &std::env::current_dir().unwrap_or_default());
+
+    // Nothing but the total, for status bars and `$(rdu --oneline ...)`
+    if args.oneline {
+        let size = scan(&args)?.borrow().size;
+        let formatted = if args.si {
+            format_size_si(size)
+        } else {
+            format_size(size)
+        };
+        println!("{}", formatted);
+        return Ok(());
+    }
+
+    let keymap =
+        match Config::load(args.config.as_deref()).and_then(|config| Keymap::new(&config.keys)) {
+            Ok(keymap) => keymap,
+            Err(e) => {
+                eprintln!("Error: {}", e);
+                process::exit(1);
+            }
+        };
+
+    if args.bench {
+        let start = Instant::now();
+        let root_node = scan(&args)?;
+        let elapsed = start.elapsed();
+        let root = root_node.borrow();
+        println!("total_size={}", root.size);
+        println!("items={}", root.item_count);
+        println!("errors={}", root.error_count);
+        println!("threads={}", num_cpus());
+        println!("elapsed_ms={:.3}", elapsed.as_secs_f64() * 1000.0);
+        return Ok(());
+    }
+
+    if let Some(file) = &args.export_jsonl {
+        let mut entries = scan_dir_streaming(&args.path, &args);
+        let count = if file.as_os_str() == "-" {
+            write_jsonl(entries.by_ref(), &mut BufWriter::new(io::stdout().lock()))?
+        } else {
+            write_jsonl(entries.by_ref(), &mut BufWriter::new(File::create(file)?))?
+        };
+        eprintln!(
+            "Exported {} entries to {} ({} errors)",
+            count,
+            file.display(),
+            entries.error_count()
+        );
+        return Ok(());
+    }
+
+    if let Some(file) = &args.export_svg {
+        let root_node = scan(&args)?;
+        if file.as_os_str() == "-" {
+            write_svg(
+                &root_node.borrow(),
+                &mut BufWriter::new(io::stdout().lock()),
+            )?;
+        } else {
+            write_svg(
+                &root_node.borrow(),
+                &mut BufWriter::new(File::create(file)?),
+            )?;
+            eprintln!("Wrote treemap to {}", file.display());
+        }
+        return Ok(());
+    }
 
-    let root_node = scan_dir(&args.path, &args);
+    if args.output_tree {
+        let root_node = scan(&args)?;
+        let mut stdout = io::stdout().lock();
+        let colors = args.color.enabled(stdout.is_terminal());
+        write_tree(
+            &root_node.borrow(),
+            &mut stdout,
+            args.max_depth,
+            args.min_size,
+            colors,
+        )?;
+        return Ok(());
+    }
+
+    // Piped or redirected output can't host the TUI, so print the summary table instead
+    let tui_is_terminal = if args.print_selection {
+        io::stderr().is_terminal()
+    } else {
+        io::stdout().is_terminal()
+    };
+    if args.summary || !tui_is_terminal {
+        let root_node = scan(&args)?;
+        let mut stdout = io::stdout().lock();
+        let colors = args.color.enabled(stdout.is_terminal());
+        write_summary(&root_node.borrow(), &mut stdout, args.min_size, colors)?;
+        return Ok(());
+    }
+
+    let snapshot = match &args.compare {
+        Some(file) => match load_snapshot(file) {
+            Ok(sizes) => Some(sizes),
+            Err(e) => {
+                eprintln!("Error: {}: {}", file.display(), e);
+                process::exit(1);
+            }
+        },
+        None => None,
+    };
+
+    // Paths from stdin and manifests are read before the TUI takes over the terminal;
+    // directory scans run inside it so their progress can be shown
+    let root_node = if args.stdin || args.from_manifest.is_some() {
+        Some(scan(&args)?)
+    } else {
+        None
+    };
+    let alt_screen = !args.no_alt_screen;
 
     // Setup panic hook before entering raw mode
-    setup_panic_hook();
+    let print_selection = args.print_selection;
+    setup_panic_hook(alt_screen, print_selection);
 
     enable_raw_mode()?;
-    let mut stdout = io::stdout();
-    execute!(stdout, EnterAlternateScreen, EnableMouseCapture)?;
-    let backend = CrosstermBackend::new(stdout);
-    let mut terminal = Terminal::new(backend)?;
+    let mut output = tui_output(print_selection);
+    execute!(output, EnableMouseCapture)?;
+    let viewport = if alt_screen {
+        execute!(output, EnterAlternateScreen)?;
+        Viewport::Fullscreen
+    } else {
+        // Draw inline so the final view stays in the scrollback
+        let (_, rows) = terminal::size()?;
+        Viewport::Inline(rows)
+    };
+    let backend = CrosstermBackend::new(output);
+    let mut terminal = Terminal::with_options(backend, TerminalOptions { viewport })?;
 
-    let app = App::new(root_node, args);
-    let res = run_app(&mut terminal, app);
+    let res = run_tui(&mut terminal, args, root_node, snapshot, &keymap);
 
     disable_raw_mode()?;
-    execute!(
-        terminal.backend_mut(),
-        LeaveAlternateScreen,
-        DisableMouseCapture
-    )?;
+    execute!(terminal.backend_mut(), DisableMouseCapture)?;
+    if alt_screen {
+        execute!(terminal.backend_mut(), LeaveAlternateScreen)?;
+    } else {
+        // Move the shell prompt below the inline viewport
+        writeln!(terminal.backend_mut())?;
+    }
     terminal.show_cursor()?;
 
-    if let Err(err) = res {
-        println!("{:?}", err)
+    // Printed only once the terminal is restored, so `$(rdu --print-selection)` gets just the path
+    if print_selection && let Ok(Some(path)) = &res {
+        let mut stdout = io::stdout().lock();
+        stdout.write_all(std::path::absolute(path)?.as_os_str().as_encoded_bytes())?;
+        stdout.write_all(b"\n")?;
     }
 
-    Ok(())
+    res.map(drop)
 }
@@ -0,0 +1,193 @@
+use crate::file_node::{FileNode, update_item_counts};
+use std::{
+    cell::RefCell,
+    collections::{HashMap, HashSet},
+    fs::File,
+    io::{self, BufRead, BufReader},
+    path::{Path, PathBuf},
+    rc::Rc,
+    time::{Duration, SystemTime},
+};
+
+/// One `path<TAB>size<TAB>mtime` line of a manifest
+struct ManifestEntry {
+    path: PathBuf,
+    size: u64,
+    mtime: Option<SystemTime>,
+    is_dir: bool,
+}
+
+/// Load a manifest file and rebuild the tree without touching the scanned filesystem
+pub fn load_manifest(path: &Path) -> io::Result<Rc<RefCell<FileNode>>> {
+    parse_manifest(BufReader::new(File::open(path)?))
+}
+
+/// Build a tree from manifest lines of the form `path<TAB>size<TAB>mtime`
+///
+/// The mtime column is optional and holds seconds since the Unix epoch (as printed by
+/// `find -printf '%p\t%s\t%T@\n'`). Paths ending in `/` or having descendants are
+/// directories; their listed size is ignored and recomputed from their contents.
+/// Blank lines and lines starting with `#` are skipped.
+pub fn parse_manifest<R: BufRead>(reader: R) -> io::Result<Rc<RefCell<FileNode>>> {
+    let mut entries = Vec::new();
+    for (line_no, line) in reader.lines().enumerate() {
+        let line = line?;
+        if line.trim().is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let entry = parse_line(&line).map_err(|msg| {
+            io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("manifest line {}: {}", line_no + 1, msg),
+            )
+        })?;
+        entries.push(entry);
+    }
+
+    let root_path = find_root(&entries).ok_or_else(|| {
+        io::Error::new(io::ErrorKind::InvalidData, "manifest contains no entries")
+    })?;
+
+    // Every ancestor of a listed path (below the root) is a directory
+    let mut dir_paths: HashSet<PathBuf> = HashSet::new();
+    for entry in &entries {
+        if entry.is_dir {
+            dir_paths.insert(entry.path.clone());
+        }
+        for ancestor in entry.path.ancestors().skip(1) {
+            if !ancestor.starts_with(&root_path) || !dir_paths.insert(ancestor.to_path_buf()) {
+                break;
+            }
+        }
+    }
+
+    let root_mtime = entries
+        .iter()
+        .find(|e| e.path == root_path)
+        .and_then(|e| e.mtime);
+    let root_node = Rc::new(RefCell::new(FileNode::new(
+        root_path.clone(),
+        display_name(&root_path),
+        0,
+        true,
+        root_mtime,
+    )));
+    let mut nodes: HashMap<PathBuf, Rc<RefCell<FileNode>>> = HashMap::new();
+    nodes.insert(root_path.clone(), Rc::clone(&root_node));
+
+    entries.sort_by_key(|e| e.path.components().count());
+    for entry in &entries {
+        if entry.path == root_path {
+            continue;
+        }
+        let is_dir = dir_paths.contains(&entry.path);
+        let size = if is_dir { 0 } else { entry.size };
+        let node = get_or_create(&mut nodes, &entry.path, &root_path);
+        let mut node = node.borrow_mut();
+        node.size = size;
+        node.is_dir = is_dir;
+        node.modified_time = entry.mtime;
+    }
+
+    propagate_sizes(&root_node);
+    update_item_counts(&root_node);
+    Ok(root_node)
+}
+
+fn parse_line(line: &str) -> Result<ManifestEntry, String> {
+    let mut fields = line.split('\t');
+    let raw_path = fields.next().unwrap_or_default();
+    if raw_path.is_empty() {
+        return Err("missing path".to_string());
+    }
+    let size = fields
+        .next()
+        .ok_or("missing size")?
+        .trim()
+        .parse::<u64>()
+        .map_err(|e| format!("invalid size: {}", e))?;
+    let mtime = match fields.next().map(str::trim) {
+        None | Some("") | Some("-") => None,
+        Some(raw) => {
+            let secs: f64 = raw.parse().map_err(|e| format!("invalid mtime: {}", e))?;
+            Some(SystemTime::UNIX_EPOCH + Duration::from_secs_f64(secs.max(0.0)))
+        }
+    };
+
+    let is_dir = raw_path.len() > 1 && raw_path.ends_with('/');
+    let trimmed = if is_dir {
+        raw_path.trim_end_matches('/')
+    } else {
+        raw_path
+    };
+    Ok(ManifestEntry {
+        path: PathBuf::from(trimmed),
+        size,
+        mtime,
+        is_dir,
+    })
+}
+
+/// The deepest directory containing every entry
+fn find_root(entries: &[ManifestEntry]) -> Option<PathBuf> {
+    let mut root = entries.first()?.path.clone();
+    for entry in entries {
+        while !entry.path.starts_with(&root) {
+            if !root.pop() {
+                break;
+            }
+        }
+    }
+
+    // A lone file can't be the root; use its parent directory instead
+    let is_leaf_file = entries.iter().any(|e| e.path == root && !e.is_dir)
+        && entries.iter().all(|e| e.path == root);
+    if is_leaf_file {
+        root.pop();
+    }
+    Some(root)
+}
+
+/// Look up the node for `path`, creating it and any missing ancestor directories
+fn get_or_create(
+    nodes: &mut HashMap<PathBuf, Rc<RefCell<FileNode>>>,
+    path: &Path,
+    root_path: &Path,
+) -> Rc<RefCell<FileNode>> {
+    if let Some(node) = nodes.get(path) {
+        return Rc::clone(node);
+    }
+
+    let node = Rc::new(RefCell::new(FileNode::new(
+        path.to_path_buf(),
+        display_name(path),
+        0,
+        true,
+        None,
+    )));
+    if let Some(parent_path) = path.parent()
+        && parent_path.starts_with(root_path)
+    {
+        let parent = get_or_create(nodes, parent_path, root_path);
+        parent.borrow_mut().children.push(Rc::clone(&node));
+    }
+    nodes.insert(path.to_path_buf(), Rc::clone(&node));
+    node
+}
+
+fn propagate_sizes(node: &Rc<RefCell<FileNode>>) -> u64 {
+    let mut node = node.borrow_mut();
+    if node.is_dir {
+        node.size = node.children.iter().map(propagate_sizes).sum();
+    }
+    // A manifest has one size per entry, used as both the apparent and the allocated size
+    node.apparent_size = node.size;
+    node.disk_size = node.size;
+    node.size
+}
+
+fn display_name(path: &Path) -> String {
+    path.file_name()
+        .map(|n| n.to_string_lossy().to_string())
+        .unwrap_or_else(|| path.display().to_string())
+}
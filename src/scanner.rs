@@ -1,11 +1,23 @@
-use crate::{args::Args, file_node::FileNode, utils::num_cpus};
+use crate::{
+    args::Args,
+    exclude::Excludes,
+    file_node::{
+        ByteTotals, FileNode, LinkTotals, SymlinkSize, update_item_counts, update_partial,
+    },
+    utils::{allocated_size, device_id, entry_size, num_cpus},
+};
 use std::{
     cell::RefCell,
-    collections::HashMap,
-    fs,
+    collections::{HashMap, HashSet, VecDeque},
+    fs, io,
     path::{Path, PathBuf},
     rc::Rc,
-    time::SystemTime,
+    sync::{
+        Arc,
+        mpsc::{self, Receiver, Sender, TryRecvError},
+    },
+    thread,
+    time::{Duration, Instant, SystemTime},
 };
 
 #[cfg(windows)]
@@ -14,142 +26,1254 @@ use crate::utils::get_drive_letter;
 #[cfg(not(windows))]
 use crate::utils::get_volume_id;
 
-/// Parallel directory scanner using jwalk
-pub fn scan_dir(path: &Path, args: &Args) -> Rc<RefCell<FileNode>> {
+/// Directories from a previous scan, keyed by path, used to skip unchanged subtrees
+///
+/// A directory's mtime only changes when entries are added, removed or renamed in it,
+/// so files modified in place below a cached directory keep their previous size.
+/// A directory is only cached when no directory below it changed either.
+#[derive(Default)]
+pub struct ScanCache {
+    mtimes: Arc<HashMap<PathBuf, SystemTime>>,
+    nodes: HashMap<PathBuf, Rc<RefCell<FileNode>>>,
+    /// The root and every directory below it still have the mtime they were scanned with
+    unchanged: bool,
+}
+
+impl ScanCache {
+    /// Build a cache from every directory below `root` that is unchanged on disk
+    ///
+    /// Every directory in the tree is stat'ed, which is still much cheaper than a rescan.
+    pub fn from_tree(root: &Rc<RefCell<FileNode>>) -> Self {
+        /// Returns whether every directory below `node` is unchanged
+        fn collect(
+            node: &Rc<RefCell<FileNode>>,
+            mtimes: &mut HashMap<PathBuf, SystemTime>,
+            nodes: &mut HashMap<PathBuf, Rc<RefCell<FileNode>>>,
+        ) -> bool {
+            let mut unchanged = true;
+            for child in &node.borrow().children {
+                let c = child.borrow();
+                if !c.is_dir {
+                    continue;
+                }
+                let below_unchanged = collect(child, mtimes, nodes);
+                // Partial directories weren't fully read, so they must be walked again
+                if below_unchanged && !c.partial && is_unchanged_on_disk(&c) {
+                    if let Some(mtime) = c.modified_time {
+                        mtimes.insert(c.path.clone(), mtime);
+                        nodes.insert(c.path.clone(), Rc::clone(child));
+                    }
+                } else {
+                    unchanged = false;
+                }
+            }
+            unchanged
+        }
+
+        let mut mtimes = HashMap::new();
+        let mut nodes = HashMap::new();
+        let below_unchanged = collect(root, &mut mtimes, &mut nodes);
+        let root = root.borrow();
+        Self {
+            mtimes: Arc::new(mtimes),
+            nodes,
+            unchanged: below_unchanged && !root.partial && is_unchanged_on_disk(&root),
+        }
+    }
+
+    /// Whether no directory in the cached tree changed since it was scanned
+    pub fn is_unchanged(&self) -> bool {
+        self.unchanged
+    }
+
+    /// Cached node for a directory whose mtime hasn't changed since it was cached
+    fn reusable(&self, path: &Path, mtime: Option<SystemTime>) -> Option<&Rc<RefCell<FileNode>>> {
+        let cached_mtime = self.mtimes.get(path)?;
+        if mtime? == *cached_mtime {
+            self.nodes.get(path)
+        } else {
+            None
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.nodes.is_empty()
+    }
+}
+
+/// A filesystem entry discovered while walking a directory tree
+#[derive(Debug, Clone)]
+pub struct ScannedEntry {
+    pub path: PathBuf,
+    /// Bytes counted for the entry, as given by [`entry_size`]
+    pub size: u64,
+    /// File length, or 0 for directories
+    pub apparent_size: u64,
+    /// Space allocated on disk, as given by [`allocated_size`]
+    pub disk_size: u64,
+    pub is_dir: bool,
+    pub modified_time: Option<SystemTime>,
+    /// Directory on another filesystem than its parent
+    pub is_mount_point: bool,
+    /// What a symbolic link that wasn't followed can count as
+    pub link_totals: Option<LinkTotals>,
+}
+
+impl ScannedEntry {
+    fn from_metadata(
+        path: PathBuf,
+        meta: &fs::Metadata,
+        disk_usage: bool,
+        symlink_size: SymlinkSize,
+    ) -> Self {
+        let link_totals = meta
+            .file_type()
+            .is_symlink()
+            .then(|| link_totals(&path, meta, disk_usage));
+        let totals = match link_totals {
+            Some(link) => link.totals(symlink_size),
+            None => ByteTotals {
+                size: entry_size(meta, disk_usage),
+                apparent_size: entry_size(meta, false),
+                disk_size: allocated_size(meta),
+            },
+        };
+        Self {
+            size: totals.size,
+            apparent_size: totals.apparent_size,
+            disk_size: totals.disk_size,
+            is_dir: meta.is_dir(),
+            modified_time: meta.modified().ok(),
+            is_mount_point: false,
+            link_totals,
+            path,
+        }
+    }
+
+    /// A node for the entry, named `name`
+    fn to_node(&self, name: String) -> FileNode {
+        let mut node = FileNode::new(
+            self.path.clone(),
+            name,
+            self.size,
+            self.is_dir,
+            self.modified_time,
+        );
+        node.apparent_size = self.apparent_size;
+        node.disk_size = self.disk_size;
+        node.is_mount_point = self.is_mount_point;
+        node.link_totals = self.link_totals;
+        node
+    }
+
+    fn totals(&self) -> ByteTotals {
+        ByteTotals {
+            size: self.size,
+            apparent_size: self.apparent_size,
+            disk_size: self.disk_size,
+        }
+    }
+}
+
+/// Totals of a symbolic link itself and of the file it points to
+///
+/// A link to a directory or a broken link has no target size: the directory's
+/// contents are only counted by following it.
+fn link_totals(path: &Path, link_meta: &fs::Metadata, disk_usage: bool) -> LinkTotals {
+    let totals = |apparent_size: u64, disk_size: u64| ByteTotals {
+        size: if disk_usage { disk_size } else { apparent_size },
+        apparent_size,
+        disk_size,
+    };
+    let target = fs::metadata(path)
+        .ok()
+        .filter(fs::Metadata::is_file)
+        .map_or_else(ByteTotals::default, |m| totals(m.len(), allocated_size(&m)));
+    LinkTotals {
+        link: totals(link_meta.len(), allocated_size(link_meta)),
+        target,
+    }
+}
+
+/// Device of every directory seen so far, to tell where a walk enters another filesystem
+struct MountDetector {
+    devices: HashMap<PathBuf, u64>,
+}
+
+impl MountDetector {
+    fn new(root_path: &Path) -> Self {
+        let devices = fs::metadata(root_path)
+            .ok()
+            .as_ref()
+            .and_then(device_id)
+            .map(|device| HashMap::from([(root_path.to_path_buf(), device)]))
+            .unwrap_or_default();
+        Self { devices }
+    }
+
+    /// Record a directory's device, returning whether it differs from its parent's
+    fn is_mount_point(&mut self, path: &Path, meta: &fs::Metadata) -> bool {
+        let Some(device) = device_id(meta).filter(|_| meta.is_dir()) else {
+            return false;
+        };
+        self.devices.insert(path.to_path_buf(), device);
+        path.parent()
+            .and_then(|parent| self.devices.get(parent))
+            .is_some_and(|&parent_device| parent_device != device)
+    }
+}
+
+/// The filesystem a scan started on, for `--one-file-system`
+struct RootFilesystem {
+    #[cfg(windows)]
+    drive: Option<char>,
+    #[cfg(not(windows))]
+    volume: Option<u64>,
+}
+
+impl RootFilesystem {
+    fn of(root_path: &Path) -> Self {
+        Self {
+            #[cfg(windows)]
+            drive: get_drive_letter(root_path),
+            #[cfg(not(windows))]
+            volume: get_volume_id(root_path),
+        }
+    }
+
+    /// Whether `entry_path` lives on another filesystem than the root
+    fn crosses(&self, entry_path: &Path) -> bool {
+        #[cfg(windows)]
+        if let (Some(root_drive), Some(entry_drive)) = (self.drive, get_drive_letter(entry_path)) {
+            return root_drive != entry_drive;
+        }
+
+        #[cfg(not(windows))]
+        if let (Some(root_vol), Some(entry_vol)) = (self.volume, get_volume_id(entry_path)) {
+            return root_vol != entry_vol;
+        }
+
+        false
+    }
+}
+
+/// Wall-clock time a walk spent in each top-level entry of its root, for `--scan-timing`
+///
+/// The time between two entries goes to the top-level entry the second one is in. Walks
+/// yield entries in walk order, so a subtree that is slow to read (a network mount, say)
+/// collects the time spent waiting for it.
+struct ScanTimer {
+    root_path: PathBuf,
+    last: Instant,
+    times: HashMap<PathBuf, Duration>,
+}
+
+impl ScanTimer {
+    fn new(root_path: &Path) -> Self {
+        Self {
+            root_path: root_path.to_path_buf(),
+            last: Instant::now(),
+            times: HashMap::new(),
+        }
+    }
+
+    /// Charge the time since the previous entry to the top-level entry `path` is in
+    fn record(&mut self, path: &Path) {
+        let now = Instant::now();
+        let Some(top) = path
+            .strip_prefix(&self.root_path)
+            .ok()
+            .and_then(|rest| rest.components().next())
+        else {
+            return;
+        };
+        *self.times.entry(self.root_path.join(top)).or_default() += now - self.last;
+        self.last = now;
+    }
+
+    /// Time per top-level entry, slowest first
+    fn sorted(&self) -> Vec<(PathBuf, Duration)> {
+        let mut times: Vec<_> = self
+            .times
+            .iter()
+            .map(|(path, time)| (path.clone(), *time))
+            .collect();
+        times.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+        times
+    }
+}
+
+/// Read errors and, of those, permission errors
+#[derive(Debug, Clone, Copy, Default)]
+struct ErrorCounts {
+    errors: usize,
+    denied: usize,
+}
+
+impl ErrorCounts {
+    fn add(&mut self, denied: bool) {
+        self.errors += 1;
+        self.denied += usize::from(denied);
+    }
+}
+
+/// Read errors of a walk, counted in total and on the directory each one happened in
+#[derive(Debug, Clone, Default)]
+struct ScanErrors {
+    total: ErrorCounts,
+    /// A directory that couldn't be listed counts on itself, an entry that couldn't be
+    /// read on its parent
+    by_dir: HashMap<PathBuf, ErrorCounts>,
+}
+
+impl ScanErrors {
+    fn record(&mut self, dir: &Path, denied: bool) {
+        self.total.add(denied);
+        self.by_dir
+            .entry(dir.to_path_buf())
+            .or_default()
+            .add(denied);
+    }
+}
+
+/// Which symbolic links a scan resolves (`--follow-links`, `--follow-dir-links`)
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum FollowLinks {
+    Never,
+    /// Only links to directories, so their contents count while linked files keep the
+    /// size of the link and files reachable both ways aren't counted twice
+    Dirs,
+    All,
+}
+
+impl FollowLinks {
+    fn of(args: &Args) -> Self {
+        if args.follow_links {
+            FollowLinks::All
+        } else if args.follow_dir_links {
+            FollowLinks::Dirs
+        } else {
+            FollowLinks::Never
+        }
+    }
+
+    /// Metadata of the entry at `path`, or of its target when the link is followed
+    fn metadata(self, path: &Path) -> io::Result<fs::Metadata> {
+        match self {
+            FollowLinks::Never => fs::symlink_metadata(path),
+            FollowLinks::All => fs::metadata(path),
+            FollowLinks::Dirs => {
+                let meta = fs::symlink_metadata(path)?;
+                if meta.file_type().is_symlink()
+                    && let Ok(target) = fs::metadata(path)
+                    && target.is_dir()
+                {
+                    return Ok(target);
+                }
+                Ok(meta)
+            }
+        }
+    }
+}
+
+/// Iterator over the entries below a root directory, yielded as the walk discovers them
+///
+/// Entries that can't be accessed are reported on stderr and counted in
+/// [`ScanEntries::error_count`] instead of being yielded.
+pub struct ScanEntries {
+    walker: jwalk::DirEntryIter<((), ())>,
+    root_path: PathBuf,
+    follow_links: FollowLinks,
+    one_file_system: bool,
+    disk_usage: bool,
+    symlink_size: SymlinkSize,
+    root_filesystem: RootFilesystem,
+    /// Only needed when the walk may cross into other filesystems
+    mounts: Option<MountDetector>,
+    timer: Option<ScanTimer>,
+    errors: ScanErrors,
+}
+
+impl ScanEntries {
+    /// Number of entries that failed to be read so far
+    pub fn error_count(&self) -> usize {
+        self.errors.total.errors
+    }
+
+    /// Of the failed entries, those that failed for lack of permission
+    pub fn denied_count(&self) -> usize {
+        self.errors.total.denied
+    }
+
+    /// Time spent in each top-level entry so far, slowest first (empty without `--scan-timing`)
+    pub fn timings(&self) -> Vec<(PathBuf, Duration)> {
+        self.timer
+            .as_ref()
+            .map(ScanTimer::sorted)
+            .unwrap_or_default()
+    }
+
+    /// The entry for `path`, also recording it for mount points and `--scan-timing`
+    fn scanned(&mut self, path: PathBuf, meta: &fs::Metadata) -> ScannedEntry {
+        let mut entry = ScannedEntry::from_metadata(path, meta, self.disk_usage, self.symlink_size);
+        entry.is_mount_point = self
+            .mounts
+            .as_mut()
+            .is_some_and(|mounts| mounts.is_mount_point(&entry.path, meta));
+        if let Some(timer) = &mut self.timer {
+            timer.record(&entry.path);
+        }
+        entry
+    }
+}
+
+impl Iterator for ScanEntries {
+    type Item = ScannedEntry;
+
+    fn next(&mut self) -> Option<ScannedEntry> {
+        loop {
+            let entry = match self.walker.next()? {
+                Ok(entry) => entry,
+                Err(e) => {
+                    // Following links, jwalk fails on a link with no target; count the link itself
+                    if let Some(path) = e.path()
+                        && let Ok(meta) = fs::symlink_metadata(path)
+                        && meta.file_type().is_symlink()
+                    {
+                        return Some(self.scanned(path.to_path_buf(), &meta));
+                    }
+                    let dir = e.path().and_then(Path::parent).unwrap_or(&self.root_path);
+                    self.errors
+                        .record(dir, e.io_error().is_some_and(is_permission_denied));
+                    eprintln!("Warning: Walk error: {}", e);
+                    continue;
+                }
+            };
+            let entry_path = entry.path();
+            // jwalk still yields a directory it couldn't list, with the failure attached
+            if let Some(e) = &entry.read_children_error {
+                self.errors
+                    .record(&entry_path, e.io_error().is_some_and(is_permission_denied));
+                eprintln!("Warning: Could not read {:?}: {}", entry_path, e);
+            }
+
+            // Skip the root itself
+            if entry_path == self.root_path {
+                continue;
+            }
+
+            if self.one_file_system && self.root_filesystem.crosses(&entry_path) {
+                continue;
+            }
+
+            let meta = self.follow_links.metadata(&entry_path);
+
+            match meta {
+                Ok(m) => return Some(self.scanned(entry_path, &m)),
+                Err(e) => {
+                    let dir = entry_path.parent().unwrap_or(&self.root_path);
+                    self.errors.record(dir, is_permission_denied(&e));
+                    eprintln!("Warning: Could not access {:?}: {}", entry_path, e);
+                }
+            }
+        }
+    }
+}
+
+/// Walk `path` in parallel and yield entries as they are discovered
+///
+/// Building a tree is left to the caller, which makes this suitable for
+/// processing very large trees without holding them in memory.
+///
+/// ```no_run
+/// use clap::Parser;
+/// use rdu::{Args, scan_dir_streaming};
+///
+/// let args = Args::parse_from(["rdu", "/var/log"]);
+/// let mut entries = scan_dir_streaming(&args.path, &args);
+/// let total: u64 = entries.by_ref().map(|entry| entry.size).sum();
+/// println!("{} bytes, {} errors", total, entries.error_count());
+/// ```
+pub fn scan_dir_streaming(path: &Path, args: &Args) -> ScanEntries {
+    walk(path, args, None)
+}
+
+fn walk(path: &Path, args: &Args, cache: Option<&ScanCache>) -> ScanEntries {
     use jwalk::WalkDir;
 
     let root_path = path.to_path_buf();
-    let mtime = fs::metadata(&root_path)
-        .ok()
-        .and_then(|m| m.modified().ok());
-    let root_name = root_path
-        .file_name()
-        .unwrap_or_default()
-        .to_string_lossy()
-        .to_string();
 
-    // Configure jwalk walker
-    let walker = WalkDir::new(&root_path)
-        .follow_links(args.follow_links)
+    // Configure jwalk walker; entries come in name order within each directory, so
+    // children end up in the same order on every scan
+    let mut walker = WalkDir::new(&root_path)
+        .follow_links(FollowLinks::of(args) != FollowLinks::Never)
         .skip_hidden(false)
+        .sort(true)
         .parallelism(jwalk::Parallelism::RayonNewPool(num_cpus()));
 
-    // Collect all entries in parallel
-    let mut entries: Vec<(PathBuf, u64, bool, Option<SystemTime>)> = Vec::new();
-    let mut error_count = 0usize;
+    let mtimes = cache
+        .filter(|c| !c.is_empty())
+        .map(|cache| Arc::clone(&cache.mtimes));
+    // Patterns were checked when parsing arguments, so building the set can't fail here
+    let excludes = Excludes::new(&args.exclude)
+        .ok()
+        .filter(|excludes| !excludes.is_empty());
+    if mtimes.is_some() || excludes.is_some() {
+        // Relative patterns are anchored at the scanned root, also when refreshing below it
+        let scan_root = args.path.clone();
+        walker = walker.process_read_dir(move |_, _, _, children| {
+            // Dropping an excluded directory here also keeps the walk out of it
+            if let Some(excludes) = &excludes {
+                children.retain(|entry| {
+                    entry
+                        .as_ref()
+                        .map_or(true, |e| !excludes.matches(&e.path(), &scan_root))
+                });
+            }
+            // Don't descend into directories whose mtime matches the cache
+            if let Some(mtimes) = &mtimes {
+                for entry in children.iter_mut().flatten() {
+                    if entry.file_type.is_dir()
+                        && let Some(cached) = mtimes.get(&entry.path())
+                        && entry.metadata().ok().and_then(|m| m.modified().ok()) == Some(*cached)
+                    {
+                        entry.read_children_path = None;
+                    }
+                }
+            }
+        });
+    }
+
+    ScanEntries {
+        walker: walker.into_iter(),
+        follow_links: FollowLinks::of(args),
+        one_file_system: args.one_file_system,
+        disk_usage: args.disk_usage,
+        symlink_size: args.symlink_size,
+        root_filesystem: RootFilesystem::of(&root_path),
+        mounts: (!args.one_file_system).then(|| MountDetector::new(&root_path)),
+        timer: args.scan_timing.then(|| ScanTimer::new(&root_path)),
+        root_path,
+        errors: ScanErrors::default(),
+    }
+}
+
+/// Breadth-first walk that stops reading directories once a time budget is spent
+///
+/// Every directory of one level is listed before the next level is started, so a
+/// walk cut short still has the top of the tree. Directories found but not read by
+/// the deadline are left in [`EstimateEntries::unread`].
+pub struct EstimateEntries {
+    queue: VecDeque<PathBuf>,
+    /// Entries of the last directory read, not yielded yet
+    pending: VecDeque<ScannedEntry>,
+    deadline: Instant,
+    scan_root: PathBuf,
+    follow_links: FollowLinks,
+    one_file_system: bool,
+    disk_usage: bool,
+    symlink_size: SymlinkSize,
+    root_filesystem: RootFilesystem,
+    mounts: Option<MountDetector>,
+    timer: Option<ScanTimer>,
+    excludes: Excludes,
+    unread: Vec<PathBuf>,
+    errors: ScanErrors,
+}
 
-    for entry_result in walker {
-        match entry_result {
-            Ok(entry) => {
-                let entry_path = entry.path();
+impl EstimateEntries {
+    pub fn new(path: &Path, args: &Args, budget: Duration) -> Self {
+        Self {
+            queue: VecDeque::from([path.to_path_buf()]),
+            pending: VecDeque::new(),
+            deadline: Instant::now() + budget,
+            scan_root: args.path.clone(),
+            follow_links: FollowLinks::of(args),
+            one_file_system: args.one_file_system,
+            disk_usage: args.disk_usage,
+            symlink_size: args.symlink_size,
+            root_filesystem: RootFilesystem::of(path),
+            mounts: (!args.one_file_system).then(|| MountDetector::new(path)),
+            timer: args.scan_timing.then(|| ScanTimer::new(path)),
+            excludes: Excludes::new(&args.exclude).unwrap_or_default(),
+            unread: Vec::new(),
+            errors: ScanErrors::default(),
+        }
+    }
 
-                // Skip the root itself
-                if entry_path == root_path {
+    pub fn error_count(&self) -> usize {
+        self.errors.total.errors
+    }
+
+    pub fn denied_count(&self) -> usize {
+        self.errors.total.denied
+    }
+
+    /// Directories that were found but not read before the deadline
+    pub fn unread(&self) -> &[PathBuf] {
+        &self.unread
+    }
+
+    /// Time spent in each top-level entry so far, slowest first (empty without `--scan-timing`)
+    pub fn timings(&self) -> Vec<(PathBuf, Duration)> {
+        self.timer
+            .as_ref()
+            .map(ScanTimer::sorted)
+            .unwrap_or_default()
+    }
+
+    fn read(&mut self, dir: &Path) {
+        let read_dir = match fs::read_dir(dir) {
+            Ok(read_dir) => read_dir,
+            Err(e) => {
+                self.errors.record(dir, is_permission_denied(&e));
+                eprintln!("Warning: Could not read {:?}: {}", dir, e);
+                return;
+            }
+        };
+        // Name order, like the full walk
+        let mut read_dir: Vec<_> = read_dir.collect();
+        read_dir.sort_by_key(|entry| entry.as_ref().ok().map(fs::DirEntry::file_name));
+        for entry in read_dir {
+            let entry_path = match entry {
+                Ok(entry) => entry.path(),
+                Err(e) => {
+                    self.errors.record(dir, is_permission_denied(&e));
+                    eprintln!("Warning: Walk error: {}", e);
                     continue;
                 }
+            };
+            if self.excludes.matches(&entry_path, &self.scan_root)
+                || (self.one_file_system && self.root_filesystem.crosses(&entry_path))
+            {
+                continue;
+            }
 
-                // One-file-system check
-                #[cfg(windows)]
-                if args.one_file_system
-                    && let (Some(root_drive), Some(entry_drive)) =
-                        (get_drive_letter(&root_path), get_drive_letter(&entry_path))
-                    && root_drive != entry_drive
-                {
-                    continue;
+            let meta = self.follow_links.metadata(&entry_path);
+            match meta {
+                Ok(m) => {
+                    if m.is_dir() {
+                        self.queue.push_back(entry_path.clone());
+                    }
+                    let mut entry = ScannedEntry::from_metadata(
+                        entry_path,
+                        &m,
+                        self.disk_usage,
+                        self.symlink_size,
+                    );
+                    entry.is_mount_point = self
+                        .mounts
+                        .as_mut()
+                        .is_some_and(|mounts| mounts.is_mount_point(&entry.path, &m));
+                    self.pending.push_back(entry);
+                }
+                Err(e) => {
+                    self.errors.record(dir, is_permission_denied(&e));
+                    eprintln!("Warning: Could not access {:?}: {}", entry_path, e);
                 }
+            }
+        }
+    }
+}
 
-                #[cfg(not(windows))]
-                if args.one_file_system
-                    && let (Some(root_vol), Some(entry_vol)) =
-                        (get_volume_id(&root_path), get_volume_id(&entry_path))
-                    && root_vol != entry_vol
-                {
-                    continue;
+impl Iterator for EstimateEntries {
+    type Item = ScannedEntry;
+
+    fn next(&mut self) -> Option<ScannedEntry> {
+        loop {
+            if let Some(entry) = self.pending.pop_front() {
+                if let Some(timer) = &mut self.timer {
+                    timer.record(&entry.path);
                 }
+                return Some(entry);
+            }
+            let dir = self.queue.pop_front()?;
+            if Instant::now() >= self.deadline {
+                self.unread.push(dir);
+                self.unread.extend(self.queue.drain(..));
+                return None;
+            }
+            self.read(&dir);
+        }
+    }
+}
 
-                let meta = if args.follow_links {
-                    fs::metadata(&entry_path)
-                } else {
-                    fs::symlink_metadata(&entry_path)
-                };
-
-                match meta {
-                    Ok(m) => {
-                        let size = if m.is_file() { m.len() } else { 0 };
-                        let mtime = m.modified().ok();
-                        entries.push((entry_path.to_path_buf(), size, m.is_dir(), mtime));
+/// Scan `path` breadth-first for at most `budget`, marking directories not read in time as partial
+///
+/// Sizes of partial directories, and of every directory above one, are lower bounds.
+pub fn scan_dir_estimate(path: &Path, args: &Args, budget: Duration) -> Rc<RefCell<FileNode>> {
+    let mut walk = EstimateEntries::new(path, args, budget);
+    let entries: Vec<ScannedEntry> = walk.by_ref().collect();
+    let root_node =
+        build_tree_with_errors(path, entries, args, &ScanCache::default(), &walk.errors);
+    root_node.borrow_mut().scan_timings = walk.timings();
+    update_partial(&root_node, &walk.unread().iter().cloned().collect());
+    root_node
+}
+
+/// Entries found between two progress messages from a [`BackgroundScan`]
+const PROGRESS_INTERVAL: usize = 1024;
+
+/// Message sent from the scanning thread of a [`BackgroundScan`]
+enum ScanMessage {
+    /// Number of entries found so far
+    Progress(usize),
+    Done {
+        entries: Vec<ScannedEntry>,
+        errors: ScanErrors,
+        /// Directories an estimate scan didn't get to
+        unread: Vec<PathBuf>,
+        timings: Vec<(PathBuf, Duration)>,
+    },
+}
+
+/// Collect `entries`, reporting progress on `sender`
+///
+/// Returns `None` when the receiver is gone because the scan was abandoned.
+fn collect_with_progress(
+    entries: impl Iterator<Item = ScannedEntry>,
+    sender: &Sender<ScanMessage>,
+) -> Option<Vec<ScannedEntry>> {
+    let mut collected = Vec::new();
+    for entry in entries {
+        collected.push(entry);
+        if collected.len() % PROGRESS_INTERVAL == 0 {
+            sender.send(ScanMessage::Progress(collected.len())).ok()?;
+        }
+    }
+    Some(collected)
+}
+
+/// A scan of one directory running on a background thread
+///
+/// The walk happens off the calling thread so a UI can report progress; the tree
+/// itself is built on the calling thread once the walk is done, since nodes aren't `Send`.
+pub struct BackgroundScan {
+    receiver: Receiver<ScanMessage>,
+    path: PathBuf,
+    args: Args,
+    items: usize,
+}
+
+impl BackgroundScan {
+    pub fn spawn(path: &Path, args: &Args) -> Self {
+        let (sender, receiver) = mpsc::channel();
+        let thread_path = path.to_path_buf();
+        let thread_args = args.clone();
+        thread::spawn(move || {
+            let done = match thread_args.estimate {
+                Some(secs) => {
+                    let budget = Duration::from_secs(secs);
+                    let mut walk = EstimateEntries::new(&thread_path, &thread_args, budget);
+                    collect_with_progress(walk.by_ref(), &sender).map(|entries| ScanMessage::Done {
+                        entries,
+                        errors: std::mem::take(&mut walk.errors),
+                        unread: walk.unread().to_vec(),
+                        timings: walk.timings(),
+                    })
+                }
+                None => {
+                    let mut walk = walk(&thread_path, &thread_args, None);
+                    collect_with_progress(walk.by_ref(), &sender).map(|entries| ScanMessage::Done {
+                        entries,
+                        errors: std::mem::take(&mut walk.errors),
+                        unread: Vec::new(),
+                        timings: walk.timings(),
+                    })
+                }
+            };
+            if let Some(done) = done {
+                let _ = sender.send(done);
+            }
+        });
+        Self {
+            receiver,
+            path: path.to_path_buf(),
+            args: args.clone(),
+            items: 0,
+        }
+    }
+
+    /// Entries found so far, as of the last [`BackgroundScan::poll`]
+    pub fn items(&self) -> usize {
+        self.items
+    }
+
+    /// Take in pending progress without blocking, returning the tree once the walk is done
+    pub fn poll(&mut self) -> Option<Rc<RefCell<FileNode>>> {
+        loop {
+            match self.receiver.try_recv() {
+                Ok(ScanMessage::Progress(items)) => self.items = items,
+                Ok(ScanMessage::Done {
+                    entries,
+                    errors,
+                    unread,
+                    timings,
+                }) => {
+                    let root = build_tree_with_errors(
+                        &self.path,
+                        entries,
+                        &self.args,
+                        &ScanCache::default(),
+                        &errors,
+                    );
+                    root.borrow_mut().scan_timings = timings;
+                    if !unread.is_empty() {
+                        update_partial(&root, &unread.into_iter().collect());
                     }
-                    Err(e) => {
-                        error_count += 1;
-                        eprintln!("Warning: Could not access {:?}: {}", entry_path, e);
+                    return Some(root);
+                }
+                Err(TryRecvError::Empty) => return None,
+                // The thread died without finishing, so report an empty tree with the failure counted
+                Err(TryRecvError::Disconnected) => {
+                    let root =
+                        build_tree(&self.path, Vec::new(), &self.args, &ScanCache::default());
+                    root.borrow_mut().error_count = 1;
+                    return Some(root);
+                }
+            }
+        }
+    }
+}
+
+/// Message sent from the scanning thread of a [`LiveScan`]
+enum LiveMessage {
+    Entries(Vec<ScannedEntry>),
+    Done {
+        errors: ScanErrors,
+        timings: Vec<(PathBuf, Duration)>,
+    },
+}
+
+/// How long the scanning thread of a [`LiveScan`] collects entries before handing them over
+const LIVE_BATCH_INTERVAL: Duration = Duration::from_millis(50);
+
+/// A scan that grows an existing tree while it runs
+///
+/// Entries are attached below the root as the walk finds them, and their sizes are
+/// added to every directory above, so the tree is consistent (if incomplete) between
+/// calls to [`LiveScan::poll`].
+pub struct LiveScan {
+    receiver: Receiver<LiveMessage>,
+    root: Rc<RefCell<FileNode>>,
+    /// Directories in the tree by path, for finding each entry's ancestors
+    dirs: HashMap<PathBuf, Rc<RefCell<FileNode>>>,
+    /// Entries that arrived before their parent directory, keyed by the parent's path
+    orphans: HashMap<PathBuf, Vec<ScannedEntry>>,
+    /// Fold files into their directory instead of adding nodes (`--dirs-only-tree`)
+    fold_files: bool,
+    items: usize,
+    done: bool,
+}
+
+impl LiveScan {
+    /// Walk the directory of `root` on a background thread, adding what it finds to `root`
+    pub fn spawn(root: &Rc<RefCell<FileNode>>, args: &Args) -> Self {
+        let (sender, receiver) = mpsc::channel();
+        let path = root.borrow().path.clone();
+        let thread_args = args.clone();
+        let thread_path = path.clone();
+        thread::spawn(move || {
+            let mut walk = walk(&thread_path, &thread_args, None);
+            let mut batch = Vec::new();
+            let mut last_sent = Instant::now();
+            for entry in walk.by_ref() {
+                batch.push(entry);
+                if last_sent.elapsed() >= LIVE_BATCH_INTERVAL {
+                    // The receiver is gone when the scan was abandoned
+                    if sender
+                        .send(LiveMessage::Entries(std::mem::take(&mut batch)))
+                        .is_err()
+                    {
+                        return;
                     }
+                    last_sent = Instant::now();
                 }
             }
-            Err(e) => {
-                error_count += 1;
-                eprintln!("Warning: Walk error: {}", e);
+            let _ = sender.send(LiveMessage::Entries(batch));
+            let _ = sender.send(LiveMessage::Done {
+                errors: std::mem::take(&mut walk.errors),
+                timings: walk.timings(),
+            });
+        });
+        Self {
+            receiver,
+            root: Rc::clone(root),
+            dirs: HashMap::from([(path, Rc::clone(root))]),
+            orphans: HashMap::new(),
+            fold_files: args.dirs_only_tree,
+            items: 0,
+            done: false,
+        }
+    }
+
+    /// Entries added to the tree so far
+    pub fn items(&self) -> usize {
+        self.items
+    }
+
+    pub fn is_done(&self) -> bool {
+        self.done
+    }
+
+    /// Add the entries received since the last call to the tree, returning how many were added
+    pub fn poll(&mut self) -> usize {
+        let before = self.items;
+        while !self.done {
+            match self.receiver.try_recv() {
+                Ok(LiveMessage::Entries(entries)) => {
+                    for entry in entries {
+                        self.attach(entry);
+                    }
+                }
+                Ok(LiveMessage::Done { errors, timings }) => {
+                    for (dir, counts) in &errors.by_dir {
+                        self.add_errors(dir, *counts);
+                    }
+                    self.root.borrow_mut().scan_timings = timings;
+                    self.done = true;
+                }
+                Err(TryRecvError::Empty) => break,
+                // The thread died without finishing, so count the failure on the root
+                Err(TryRecvError::Disconnected) => {
+                    self.root.borrow_mut().error_count += 1;
+                    self.done = true;
+                }
             }
         }
+        self.items - before
     }
 
+    /// Count `counts` on the directory at `dir` and on every directory above it
+    fn add_errors(&self, dir: &Path, counts: ErrorCounts) {
+        for path in dir.ancestors() {
+            if let Some(node) = self.dirs.get(path) {
+                let mut node = node.borrow_mut();
+                node.error_count += counts.errors;
+                node.denied_count += counts.denied;
+            }
+        }
+    }
+
+    /// Link `entry` to its parent and add its totals to every directory above it
+    fn attach(&mut self, entry: ScannedEntry) {
+        let Some(parent_path) = entry.path.parent().map(Path::to_path_buf) else {
+            return;
+        };
+        let Some(parent) = self.dirs.get(&parent_path).cloned() else {
+            self.orphans.entry(parent_path).or_default().push(entry);
+            return;
+        };
+
+        let node = if self.fold_files && !entry.is_dir {
+            let mut parent = parent.borrow_mut();
+            parent.folded_files += 1;
+            parent.folded_size += entry.size;
+            None
+        } else {
+            let name = entry
+                .path
+                .file_name()
+                .unwrap_or_default()
+                .to_string_lossy()
+                .to_string();
+            let node = Rc::new(RefCell::new(entry.to_node(name)));
+            parent.borrow_mut().children.push(Rc::clone(&node));
+            Some(node)
+        };
+        let totals = entry.totals();
+        self.items += 1;
+
+        let mut ancestor_path = Some(parent_path.as_path());
+        while let Some(path) = ancestor_path
+            && let Some(ancestor) = self.dirs.get(path)
+        {
+            let mut ancestor = ancestor.borrow_mut();
+            ancestor.add_totals(totals);
+            ancestor.item_count += 1;
+            ancestor_path = path.parent();
+        }
+
+        if let Some(node) = node.filter(|_| entry.is_dir) {
+            self.dirs.insert(entry.path.clone(), node);
+            for orphan in self.orphans.remove(&entry.path).unwrap_or_default() {
+                self.attach(orphan);
+            }
+        }
+    }
+}
+
+/// Whether the mtime of `node` on disk is still the one it was scanned with
+fn is_unchanged_on_disk(node: &FileNode) -> bool {
+    let on_disk = fs::metadata(&node.path).and_then(|m| m.modified()).ok();
+    on_disk.is_some() && on_disk == node.modified_time
+}
+
+/// Whether an error comes from a lack of permission, which more privileges could fix
+fn is_permission_denied(error: &io::Error) -> bool {
+    error.kind() == io::ErrorKind::PermissionDenied
+}
+
+/// Parallel directory scanner using jwalk
+pub fn scan_dir(path: &Path, args: &Args) -> Rc<RefCell<FileNode>> {
+    scan_dir_cached(path, args, &ScanCache::default())
+}
+
+/// Parallel directory scanner that reuses unchanged directories from `cache`
+pub fn scan_dir_cached(path: &Path, args: &Args, cache: &ScanCache) -> Rc<RefCell<FileNode>> {
+    let mut walk = walk(path, args, Some(cache));
+    let entries: Vec<ScannedEntry> = walk.by_ref().collect();
+    let root_node = build_tree_with_errors(path, entries, args, cache, &walk.errors);
+    root_node.borrow_mut().scan_timings = walk.timings();
+    root_node
+}
+
+/// Build a tree from the entries found below `root_path`
+///
+/// Directories whose mtime matches `cache` reuse the cached subtree and size.
+/// In disk-usage mode the root's own allocated size is included in the total.
+pub fn build_tree(
+    root_path: &Path,
+    entries: Vec<ScannedEntry>,
+    args: &Args,
+    cache: &ScanCache,
+) -> Rc<RefCell<FileNode>> {
+    build_tree_with_errors(root_path, entries, args, cache, &ScanErrors::default())
+}
+
+/// Build a tree like [`build_tree`], counting the walk's `errors` on each directory
+///
+/// A directory's error counts include those of every directory below it, so they
+/// say how many entries its total is missing.
+fn build_tree_with_errors(
+    root_path: &Path,
+    mut entries: Vec<ScannedEntry>,
+    args: &Args,
+    cache: &ScanCache,
+    errors: &ScanErrors,
+) -> Rc<RefCell<FileNode>> {
+    let root_path = root_path.to_path_buf();
+    let root_meta = fs::metadata(&root_path).ok();
+    let mtime = root_meta.as_ref().and_then(|m| m.modified().ok());
+    let root_size = root_meta
+        .as_ref()
+        .filter(|_| args.disk_usage)
+        .map_or(0, |m| entry_size(m, true));
+    // A root like `/` has no name of its own, so it's shown as the whole path
+    let root_name = root_path
+        .file_name()
+        .unwrap_or(root_path.as_os_str())
+        .to_string_lossy()
+        .to_string();
+
     // Build tree structure from flat entries
     let mut nodes: HashMap<PathBuf, Rc<RefCell<FileNode>>> = HashMap::new();
 
     // Create root node
-    let root_node = Rc::new(RefCell::new(FileNode::new(
-        root_path.clone(),
-        root_name,
-        0,
-        true,
-        mtime,
-    )));
-    root_node.borrow_mut().error_count = error_count;
+    let mut root = FileNode::new(root_path.clone(), root_name, root_size, true, mtime);
+    root.apparent_size = 0;
+    root.disk_size = root_meta.as_ref().map_or(0, allocated_size);
+    let root_node = Rc::new(RefCell::new(root));
     nodes.insert(root_path.clone(), Rc::clone(&root_node));
 
+    if filters_files(args) {
+        entries = filter_files(&root_path, entries, args, cache);
+    }
+
     // Sort entries by path depth (parents before children)
-    let mut sorted_entries = entries;
-    sorted_entries.sort_by(|a, b| a.0.components().count().cmp(&b.0.components().count()));
+    entries.sort_by_key(|e| e.path.components().count());
 
     // Create all nodes and link children to parents
-    for (entry_path, size, is_dir, mtime) in &sorted_entries {
-        let name = entry_path
+    for entry in &entries {
+        // Files only add up in their directory when they aren't kept
+        if args.dirs_only_tree && !entry.is_dir {
+            if let Some(parent_path) = entry.path.parent()
+                && let Some(parent_node) = nodes.get(parent_path)
+            {
+                let mut parent = parent_node.borrow_mut();
+                parent.folded_files += 1;
+                parent.folded_size += entry.size;
+                parent.add_totals(entry.totals());
+            }
+            continue;
+        }
+        let name = entry
+            .path
             .file_name()
             .unwrap_or_default()
             .to_string_lossy()
             .to_string();
 
-        let node = Rc::new(RefCell::new(FileNode::new(
-            entry_path.clone(),
-            name,
-            *size,
-            *is_dir,
-            *mtime,
-        )));
-        nodes.insert(entry_path.clone(), Rc::clone(&node));
+        // Unchanged directories keep their cached subtree and size
+        let node = match cache
+            .reusable(&entry.path, entry.modified_time)
+            .filter(|_| entry.is_dir)
+        {
+            Some(cached) => Rc::clone(cached),
+            None => Rc::new(RefCell::new(entry.to_node(name))),
+        };
+        nodes.insert(entry.path.clone(), Rc::clone(&node));
 
         // Add to parent (but don't update size yet for directories)
-        if let Some(parent_path) = entry_path.parent()
+        if let Some(parent_path) = entry.path.parent()
             && let Some(parent_node) = nodes.get(parent_path)
         {
             parent_node.borrow_mut().children.push(Rc::clone(&node));
             // Only add file sizes directly - directory sizes will be propagated later
-            if !*is_dir {
-                parent_node.borrow_mut().size += size;
+            if !entry.is_dir {
+                let totals = node.borrow().totals();
+                parent_node.borrow_mut().add_totals(totals);
             }
         }
     }
 
-    // Propagate directory sizes from deepest to shallowest
-    for (entry_path, _, is_dir, _) in sorted_entries.iter().rev() {
-        if *is_dir && let Some(node) = nodes.get(entry_path) {
-            let dir_size = node.borrow().size;
-            if let Some(parent_path) = entry_path.parent()
+    // Errors count on their directory, or on the closest one kept when a filter left it out
+    for (dir, counts) in &errors.by_dir {
+        if let Some(node) = dir.ancestors().find_map(|path| nodes.get(path)) {
+            let mut node = node.borrow_mut();
+            node.error_count += counts.errors;
+            node.denied_count += counts.denied;
+        }
+    }
+
+    // Propagate directory sizes and error counts from deepest to shallowest
+    for entry in entries.iter().rev() {
+        if entry.is_dir
+            && let Some(node) = nodes.get(&entry.path)
+        {
+            let node = node.borrow();
+            if let Some(parent_path) = entry.path.parent()
                 && let Some(parent_node) = nodes.get(parent_path)
             {
-                parent_node.borrow_mut().size += dir_size;
+                let mut parent = parent_node.borrow_mut();
+                parent.add_totals(node.totals());
+                parent.error_count += node.error_count;
+                parent.denied_count += node.denied_count;
+            }
+        }
+    }
+
+    update_item_counts(&root_node);
+    root_node
+}
+
+/// Whether a file's extension is one of `extensions` (compared case-insensitively)
+pub(crate) fn extension_matches(path: &Path, extensions: &[String]) -> bool {
+    path.extension().is_some_and(|ext| {
+        extensions
+            .iter()
+            .any(|e| ext.eq_ignore_ascii_case(e.trim_start_matches('.')))
+    })
+}
+
+/// Whether only some files are counted (`--ext`, `--newer-than`, `--older-than`)
+fn filters_files(args: &Args) -> bool {
+    !args.ext.is_empty() || args.newer_than.is_some() || args.older_than.is_some()
+}
+
+/// Whether a file passes `--ext`, `--newer-than` and `--older-than`
+///
+/// Files without a modification time never pass a time filter.
+fn file_matches(path: &Path, modified_time: Option<SystemTime>, args: &Args) -> bool {
+    (args.ext.is_empty() || extension_matches(path, &args.ext))
+        && args
+            .newer_than
+            .is_none_or(|cutoff| modified_time.is_some_and(|t| t >= cutoff))
+        && args
+            .older_than
+            .is_none_or(|cutoff| modified_time.is_some_and(|t| t < cutoff))
+}
+
+/// Drop files that don't pass the file filters and directories left without any file that does
+///
+/// Reused cache entries were filtered when they were scanned, so a non-empty one counts
+/// as containing matches even though the walk didn't descend into it.
+fn filter_files(
+    root_path: &Path,
+    entries: Vec<ScannedEntry>,
+    args: &Args,
+    cache: &ScanCache,
+) -> Vec<ScannedEntry> {
+    let keeps_contents = |entry: &ScannedEntry| {
+        if entry.is_dir {
+            cache
+                .reusable(&entry.path, entry.modified_time)
+                .is_some_and(|node| !node.borrow().children.is_empty())
+        } else {
+            file_matches(&entry.path, entry.modified_time, args)
+        }
+    };
+
+    let mut kept_dirs: HashSet<PathBuf> = HashSet::new();
+    for entry in entries.iter().filter(|e| keeps_contents(e)) {
+        let start = if entry.is_dir {
+            Some(entry.path.as_path())
+        } else {
+            entry.path.parent()
+        };
+        for dir in start.into_iter().flat_map(Path::ancestors) {
+            if dir == root_path || !kept_dirs.insert(dir.to_path_buf()) {
+                break;
+            }
+        }
+    }
+
+    entries
+        .into_iter()
+        .filter(|e| {
+            if e.is_dir {
+                kept_dirs.contains(&e.path)
+            } else {
+                file_matches(&e.path, e.modified_time, args)
+            }
+        })
+        .collect()
+}
+
+/// Build a flat root node from an explicit list of paths without walking directories
+///
+/// Relative paths are resolved against the current directory. Paths that can't be
+/// accessed are reported and counted as errors on the root.
+pub fn scan_paths<I>(paths: I, args: &Args) -> Rc<RefCell<FileNode>>
+where
+    I: IntoIterator<Item = PathBuf>,
+{
+    let base = std::env::current_dir().unwrap_or_default();
+    let excludes = Excludes::new(&args.exclude).unwrap_or_default();
+    let root_node = Rc::new(RefCell::new(FileNode::new(
+        base.clone(),
+        "(stdin)".to_string(),
+        0,
+        true,
+        None,
+    )));
+
+    let mut root = root_node.borrow_mut();
+    for path in paths {
+        if excludes.matches(&path, &args.path) {
+            continue;
+        }
+        let full_path = base.join(&path);
+        let meta = FollowLinks::of(args).metadata(&full_path);
+
+        match meta {
+            Ok(m) if !m.is_dir() && !file_matches(&path, m.modified().ok(), args) => {}
+            Ok(m) => {
+                let node =
+                    ScannedEntry::from_metadata(full_path, &m, args.disk_usage, args.symlink_size)
+                        .to_node(path.to_string_lossy().to_string());
+                root.add_totals(node.totals());
+                root.children.push(Rc::new(RefCell::new(node)));
+            }
+            Err(e) => {
+                root.error_count += 1;
+                root.denied_count += usize::from(is_permission_denied(&e));
+                eprintln!("Warning: Could not access {:?}: {}", path, e);
             }
         }
     }
+    drop(root);
 
+    update_item_counts(&root_node);
     root_node
 }
@@ -1,25 +1,70 @@
-#[derive(Debug, Clone, Copy, PartialEq)]
+use clap::ValueEnum;
+
+#[derive(ValueEnum, Debug, Clone, Copy, PartialEq)]
 pub enum SortMode {
+    /// Size (or item count in inode view), largest first
     Size,
+    /// Modification time, newest first
+    #[value(name = "mtime")]
     ModifiedTime,
+    /// Number of direct children, most first
+    #[value(name = "count")]
     ItemCount,
+    /// Name, alphabetically
+    Name,
 }
 
 impl SortMode {
-    #[allow(dead_code)] // May be used for cycling through modes
     pub fn next(&self) -> Self {
         match self {
             SortMode::Size => SortMode::ModifiedTime,
             SortMode::ModifiedTime => SortMode::ItemCount,
-            SortMode::ItemCount => SortMode::Size,
+            SortMode::ItemCount => SortMode::Name,
+            SortMode::Name => SortMode::Size,
         }
     }
 
+    /// Direction a mode starts in: largest/newest first, names alphabetically
+    pub fn default_ascending(&self) -> bool {
+        *self == SortMode::Name
+    }
+
     pub fn name(&self) -> &'static str {
         match self {
             SortMode::Size => "size",
             SortMode::ModifiedTime => "mtime",
             SortMode::ItemCount => "count",
+            SortMode::Name => "name",
+        }
+    }
+}
+
+/// Where directories are placed relative to files, independent of the sort mode
+#[derive(ValueEnum, Debug, Clone, Copy, PartialEq, Default)]
+pub enum DirGrouping {
+    /// Directories and files are sorted together
+    #[default]
+    Mixed,
+    /// Directories are listed before files
+    First,
+    /// Directories are listed after files
+    Last,
+}
+
+impl DirGrouping {
+    pub fn next(&self) -> Self {
+        match self {
+            DirGrouping::Mixed => DirGrouping::First,
+            DirGrouping::First => DirGrouping::Last,
+            DirGrouping::Last => DirGrouping::Mixed,
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            DirGrouping::Mixed => "mixed",
+            DirGrouping::First => "dirs first",
+            DirGrouping::Last => "dirs last",
         }
     }
 }
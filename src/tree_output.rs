@@ -0,0 +1,173 @@
+use crate::{
+    colors::{COLOR_DIRECTORY, COLOR_SIZE, paint},
+    file_node::FileNode,
+    utils::{format_size, format_size_aligned, format_size_du},
+};
+use std::{
+    cell::RefCell,
+    io::{self, Write},
+    rc::Rc,
+};
+
+/// Write `entries` as `du -h` output: one `SIZE<tab>PATH` line each, in the given order
+pub fn write_du<W: Write>(entries: &[Rc<RefCell<FileNode>>], writer: &mut W) -> io::Result<()> {
+    for entry in entries {
+        let entry = entry.borrow();
+        writeln!(
+            writer,
+            "{}\t{}",
+            format_size_du(entry.size),
+            entry.path.display()
+        )?;
+    }
+    Ok(())
+}
+
+/// Write an ASCII tree of `root` with sizes, similar to `tree --du -h`
+///
+/// Children are listed largest first. Entries deeper than `max_depth` or smaller
+/// than `min_size` are omitted, but still count toward their parent's size.
+/// Sizes and directory names are colored with ANSI escapes when `colors` is set.
+pub fn write_tree<W: Write>(
+    root: &FileNode,
+    writer: &mut W,
+    max_depth: Option<usize>,
+    min_size: u64,
+    colors: bool,
+) -> io::Result<()> {
+    writeln!(
+        writer,
+        "[{}]  {}{}",
+        paint(
+            &format!("{:>10}", format_size(root.size)),
+            COLOR_SIZE,
+            colors
+        ),
+        paint(&root.path.display().to_string(), COLOR_DIRECTORY, colors),
+        partial_suffix(root)
+    )?;
+    write_children(root, writer, "", 1, max_depth, min_size, colors)
+}
+
+fn write_children<W: Write>(
+    node: &FileNode,
+    writer: &mut W,
+    prefix: &str,
+    depth: usize,
+    max_depth: Option<usize>,
+    min_size: u64,
+    colors: bool,
+) -> io::Result<()> {
+    if max_depth.is_some_and(|max| depth > max) {
+        return Ok(());
+    }
+
+    let mut children: Vec<_> = node
+        .children
+        .iter()
+        .filter(|c| c.borrow().size >= min_size)
+        .collect();
+    children.sort_by(|a, b| {
+        let (a, b) = (a.borrow(), b.borrow());
+        b.size.cmp(&a.size).then_with(|| a.name.cmp(&b.name))
+    });
+
+    let count = children.len();
+    for (i, child) in children.into_iter().enumerate() {
+        let child = child.borrow();
+        let is_last = i + 1 == count;
+        let connector = if is_last { "└── " } else { "├── " };
+        writeln!(
+            writer,
+            "{}{}[{}]  {}{}",
+            prefix,
+            connector,
+            paint(
+                &format!("{:>10}", format_size(child.size)),
+                COLOR_SIZE,
+                colors
+            ),
+            name(&child, colors),
+            partial_suffix(&child)
+        )?;
+
+        if child.is_dir {
+            let child_prefix = format!("{}{}", prefix, if is_last { "    " } else { "│   " });
+            write_children(
+                &child,
+                writer,
+                &child_prefix,
+                depth + 1,
+                max_depth,
+                min_size,
+                colors,
+            )?;
+        }
+    }
+    Ok(())
+}
+
+/// Write a table of `root`'s direct children with sizes and percentages, largest first
+///
+/// Entries smaller than `min_size` are omitted, but still count toward the total.
+/// Sizes and directory names are colored with ANSI escapes when `colors` is set.
+pub fn write_summary<W: Write>(
+    root: &FileNode,
+    writer: &mut W,
+    min_size: u64,
+    colors: bool,
+) -> io::Result<()> {
+    let mut children: Vec<_> = root
+        .children
+        .iter()
+        .filter(|c| c.borrow().size >= min_size)
+        .collect();
+    children.sort_by(|a, b| {
+        let (a, b) = (a.borrow(), b.borrow());
+        b.size.cmp(&a.size).then_with(|| a.name.cmp(&b.name))
+    });
+
+    let percent = |size: u64| {
+        if root.size > 0 {
+            size as f64 / root.size as f64 * 100.0
+        } else {
+            0.0
+        }
+    };
+    writeln!(writer, "{:>10}  {:>6}  Name", "Size", "%")?;
+    for child in children {
+        let child = child.borrow();
+        let suffix = if child.is_dir { "/" } else { "" };
+        writeln!(
+            writer,
+            "{}  {:>5.1}%  {}{}{}",
+            paint(&format_size_aligned(child.size), COLOR_SIZE, colors),
+            percent(child.size),
+            name(&child, colors),
+            suffix,
+            partial_suffix(&child)
+        )?;
+    }
+    writeln!(
+        writer,
+        "{}  {:>5.1}%  {} (total{})",
+        paint(&format_size_aligned(root.size), COLOR_SIZE, colors),
+        100.0,
+        root.path.display(),
+        if root.partial { ", estimate" } else { "" }
+    )
+}
+
+/// Entry name, in the directory color for directories
+fn name(node: &FileNode, colors: bool) -> String {
+    if node.is_dir {
+        paint(&node.name, COLOR_DIRECTORY, colors)
+    } else {
+        node.name.clone()
+    }
+}
+
+/// Marks directories whose size is a lower bound after an `--estimate` scan
+fn partial_suffix(node: &FileNode) -> &'static str {
+    if node.partial { " (partial)" } else { "" }
+}
@@ -0,0 +1,282 @@
+use crate::{file_node::FileNode, utils::format_size};
+use std::io::{self, Write};
+
+/// Size of the canvas written by [`write_svg`], in pixels
+pub const SVG_WIDTH: f64 = 1280.0;
+pub const SVG_HEIGHT: f64 = 800.0;
+
+/// Height of the label strip above the contents of a directory
+const DIR_HEADER: f64 = 14.0;
+/// Space between a directory's border and its contents
+const DIR_PADDING: f64 = 2.0;
+/// Rectangles smaller than this (in square pixels) are left out
+const MIN_AREA: f64 = 4.0;
+
+/// Directory fills by depth below the root, repeating for deeper levels
+const DEPTH_COLORS: [&str; 6] = [
+    "#2e3440", "#3b4252", "#434c5e", "#4c566a", "#3b4a5a", "#465262",
+];
+/// File fills, picked by a hash of the extension so each extension keeps its color
+const EXTENSION_COLORS: [&str; 12] = [
+    "#5e81ac", "#88c0d0", "#8fbcbb", "#a3be8c", "#ebcb8b", "#d08770", "#bf616a", "#b48ead",
+    "#81a1c1", "#c7a26b", "#6f9e78", "#9a7fb8",
+];
+const NO_EXTENSION_COLOR: &str = "#7b8394";
+
+/// An axis-aligned rectangle on the treemap canvas
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct Rect {
+    pub x: f64,
+    pub y: f64,
+    pub width: f64,
+    pub height: f64,
+}
+
+impl Rect {
+    pub fn new(x: f64, y: f64, width: f64, height: f64) -> Self {
+        Self {
+            x,
+            y,
+            width,
+            height,
+        }
+    }
+
+    pub fn area(&self) -> f64 {
+        self.width * self.height
+    }
+
+    /// The rectangle shrunk by `top` at the top and `side` on every side, or `None` if nothing is left
+    fn inset(&self, top: f64, side: f64) -> Option<Rect> {
+        let inner = Rect::new(
+            self.x + side,
+            self.y + top + side,
+            self.width - 2.0 * side,
+            self.height - top - 2.0 * side,
+        );
+        (inner.width > 0.0 && inner.height > 0.0).then_some(inner)
+    }
+}
+
+/// Lay out `sizes` in `area` as a squarified treemap, returning one rectangle per size in the same order
+///
+/// Rectangles get a share of `area` proportional to their size and tile it without
+/// overlapping. Larger sizes are placed first, and rows are grown while that keeps
+/// their rectangles close to square (Bruls, Huizing and van Wijk). Zero sizes get
+/// an empty rectangle.
+///
+/// ```
+/// use rdu::treemap::{Rect, squarify};
+///
+/// let area = Rect::new(0.0, 0.0, 600.0, 400.0);
+/// let rects = squarify(&[6, 6, 4, 3, 2, 2, 1], area);
+///
+/// let total: f64 = rects.iter().map(Rect::area).sum();
+/// assert!((total - area.area()).abs() < 1e-6);
+/// assert!((rects[0].area() - area.area() * 6.0 / 24.0).abs() < 1e-6);
+///
+/// for (i, a) in rects.iter().enumerate() {
+///     assert!(a.x >= -1e-9 && a.y >= -1e-9);
+///     assert!(a.x + a.width <= 600.0 + 1e-9 && a.y + a.height <= 400.0 + 1e-9);
+///     for b in &rects[i + 1..] {
+///         let overlap_w = (a.x + a.width).min(b.x + b.width) - a.x.max(b.x);
+///         let overlap_h = (a.y + a.height).min(b.y + b.height) - a.y.max(b.y);
+///         assert!(overlap_w <= 1e-9 || overlap_h <= 1e-9);
+///     }
+/// }
+/// ```
+pub fn squarify(sizes: &[u64], area: Rect) -> Vec<Rect> {
+    let mut rects = vec![Rect::new(area.x, area.y, 0.0, 0.0); sizes.len()];
+    let total: u64 = sizes.iter().sum();
+    if total == 0 || area.area() <= 0.0 {
+        return rects;
+    }
+    let scale = area.area() / total as f64;
+
+    let mut order: Vec<(usize, f64)> = sizes
+        .iter()
+        .enumerate()
+        .filter(|(_, size)| **size > 0)
+        .map(|(i, size)| (i, *size as f64 * scale))
+        .collect();
+    order.sort_by(|a, b| b.1.total_cmp(&a.1));
+
+    let mut rest = area;
+    let mut row: Vec<(usize, f64)> = Vec::new();
+    for item in order {
+        let side = rest.width.min(rest.height);
+        if !row.is_empty() && worst_ratio(&row, Some(item.1), side) > worst_ratio(&row, None, side)
+        {
+            lay_out_row(&row, &mut rest, &mut rects);
+            row.clear();
+        }
+        row.push(item);
+    }
+    lay_out_row(&row, &mut rest, &mut rects);
+    rects
+}
+
+/// Worst aspect ratio in a row of `areas` (plus `extra`) laid along a side of length `side`
+fn worst_ratio(row: &[(usize, f64)], extra: Option<f64>, side: f64) -> f64 {
+    let areas = || row.iter().map(|(_, area)| *area).chain(extra);
+    let sum: f64 = areas().sum();
+    let max = areas().fold(0.0, f64::max);
+    let min = areas().fold(f64::INFINITY, f64::min);
+    let side_sq = side * side;
+    let sum_sq = sum * sum;
+    (side_sq * max / sum_sq).max(sum_sq / (side_sq * min))
+}
+
+/// Place a row along the shorter side of `rest` and remove the strip it takes from `rest`
+fn lay_out_row(row: &[(usize, f64)], rest: &mut Rect, rects: &mut [Rect]) {
+    let sum: f64 = row.iter().map(|(_, area)| area).sum();
+    if sum <= 0.0 {
+        return;
+    }
+    if rest.width >= rest.height {
+        // A column on the left
+        let thickness = sum / rest.height;
+        let mut y = rest.y;
+        for (i, area) in row {
+            let height = area / thickness;
+            if let Some(rect) = rects.get_mut(*i) {
+                *rect = Rect::new(rest.x, y, thickness, height);
+            }
+            y += height;
+        }
+        rest.x += thickness;
+        rest.width = (rest.width - thickness).max(0.0);
+    } else {
+        // A row along the top
+        let thickness = sum / rest.width;
+        let mut x = rest.x;
+        for (i, area) in row {
+            let width = area / thickness;
+            if let Some(rect) = rects.get_mut(*i) {
+                *rect = Rect::new(x, rest.y, width, thickness);
+            }
+            x += width;
+        }
+        rest.y += thickness;
+        rest.height = (rest.height - thickness).max(0.0);
+    }
+}
+
+/// Write `root` as an SVG treemap of [`SVG_WIDTH`] by [`SVG_HEIGHT`] pixels
+///
+/// Every entry is a rectangle whose area is proportional to its size, nested in
+/// its directory. Directories are shaded by depth and files colored by extension;
+/// rectangles large enough are labeled with the name and size, and every one
+/// shows its path and size as a tooltip.
+pub fn write_svg<W: Write>(root: &FileNode, writer: &mut W) -> io::Result<()> {
+    writeln!(
+        writer,
+        r#"<svg xmlns="http://www.w3.org/2000/svg" width="{w}" height="{h}" viewBox="0 0 {w} {h}" font-family="sans-serif" font-size="11">"#,
+        w = SVG_WIDTH,
+        h = SVG_HEIGHT
+    )?;
+    write_node(root, Rect::new(0.0, 0.0, SVG_WIDTH, SVG_HEIGHT), 0, writer)?;
+    writeln!(writer, "</svg>")?;
+    writer.flush()
+}
+
+fn write_node<W: Write>(
+    node: &FileNode,
+    rect: Rect,
+    depth: usize,
+    writer: &mut W,
+) -> io::Result<()> {
+    let fill = if node.is_dir {
+        DEPTH_COLORS
+            .get(depth % DEPTH_COLORS.len())
+            .copied()
+            .unwrap_or(NO_EXTENSION_COLOR)
+    } else {
+        extension_color(node)
+    };
+    writeln!(
+        writer,
+        r##"<g><title>{} ({})</title><rect x="{:.2}" y="{:.2}" width="{:.2}" height="{:.2}" fill="{}" stroke="#1d2128" stroke-width="0.5"/>"##,
+        escape(&node.path.to_string_lossy()),
+        format_size(node.size),
+        rect.x,
+        rect.y,
+        rect.width,
+        rect.height,
+        fill
+    )?;
+    write_label(node, rect, writer)?;
+    writeln!(writer, "</g>")?;
+
+    if !node.is_dir {
+        return Ok(());
+    }
+    let Some(inner) = rect.inset(DIR_HEADER, DIR_PADDING) else {
+        return Ok(());
+    };
+    // What the children don't account for (the directory's own entry, folded files)
+    // keeps its share of the area, left empty
+    let children_size: u64 = node.children.iter().map(|c| c.borrow().size).sum();
+    let mut sizes: Vec<u64> = node.children.iter().map(|c| c.borrow().size).collect();
+    sizes.push(node.size.saturating_sub(children_size));
+    let rects = squarify(&sizes, inner);
+    for (child, child_rect) in node.children.iter().zip(rects) {
+        if child_rect.area() >= MIN_AREA {
+            write_node(&child.borrow(), child_rect, depth + 1, writer)?;
+        }
+    }
+    Ok(())
+}
+
+/// Name and size in the top left corner if there's room for a line, or as much of the name as fits
+fn write_label<W: Write>(node: &FileNode, rect: Rect, writer: &mut W) -> io::Result<()> {
+    // Rough width of a character at font-size 11
+    const CHAR_WIDTH: f64 = 6.5;
+    if rect.height < DIR_HEADER || rect.width < 4.0 * CHAR_WIDTH {
+        return Ok(());
+    }
+    let fits = ((rect.width - 6.0) / CHAR_WIDTH) as usize;
+    let full = format!("{} {}", node.name, format_size(node.size));
+    let label: String = if full.chars().count() <= fits {
+        full
+    } else {
+        node.name.chars().take(fits).collect()
+    };
+    // Directory fills are dark and file fills light
+    let color = if node.is_dir { "#eceff4" } else { "#1d2128" };
+    writeln!(
+        writer,
+        r#"<text x="{:.2}" y="{:.2}" fill="{}">{}</text>"#,
+        rect.x + 3.0,
+        rect.y + 11.0,
+        color,
+        escape(&label)
+    )
+}
+
+/// Fill of a file, the same for every file with the same extension
+fn extension_color(node: &FileNode) -> &'static str {
+    let Some(ext) = node.path.extension() else {
+        return NO_EXTENSION_COLOR;
+    };
+    // FNV-1a, so colors don't change between runs or builds
+    let hash = ext
+        .to_string_lossy()
+        .to_lowercase()
+        .bytes()
+        .fold(0xcbf2_9ce4_8422_2325_u64, |hash, byte| {
+            (hash ^ u64::from(byte)).wrapping_mul(0x0100_0000_01b3)
+        });
+    EXTENSION_COLORS
+        .get((hash % EXTENSION_COLORS.len() as u64) as usize)
+        .copied()
+        .unwrap_or(NO_EXTENSION_COLOR)
+}
+
+/// Escape text for use in SVG content and attributes
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
@@ -1,50 +1,153 @@
 use crate::{
-    app::App,
+    app::{App, Overlay, PercentBasis, PromptKind},
     colors::*,
-    utils::{format_size, render_bar},
+    columns::ColumnKind,
+    file_node::FileNode,
+    utils::{
+        DiskSpace, TimeFormat, format_age, format_bytes_grouped, format_size, format_size_aligned,
+        format_size_delta, format_time, format_timestamp, render_bar, render_bar_ascii,
+        truncate_with_ellipsis,
+    },
 };
 use ratatui::{
     Frame,
-    layout::{Constraint, Direction, Layout},
+    buffer::Buffer,
+    layout::{Alignment, Constraint, Direction, Layout, Rect},
     style::{Color, Modifier, Style},
     text::{Line, Span},
-    widgets::{Block, Borders, Clear, List, ListItem, Paragraph},
+    widgets::{Block, Borders, Clear, List, ListItem, Paragraph, Wrap},
 };
+use std::{path::Path, time::Duration};
+use unicode_width::UnicodeWidthStr;
+
+/// Separator drawn between list columns
+const COLUMN_SEPARATOR: &str = " | ";
+
+/// Smallest terminal size the regular layout can be drawn in
+const MIN_WIDTH: u16 = 30;
+const MIN_HEIGHT: u16 = 5;
 
 pub fn ui(f: &mut Frame, app: &mut App) {
+    let area = f.area();
+    if area.width < MIN_WIDTH || area.height < MIN_HEIGHT {
+        // Nothing of the overlay is on screen, so mouse handling mustn't use its old area
+        app.overlay_area = Rect::default();
+        app.header_columns.clear();
+        render_too_small(f);
+        return;
+    }
+
+    // Give the list the row back when the terminal is at its smallest
+    let show_root_bar = app.show_root_bar && area.height > MIN_HEIGHT;
     let chunks = Layout::default()
         .direction(Direction::Vertical)
         .constraints([
-            Constraint::Length(1), // Title bar
-            Constraint::Length(1), // Directory info
-            Constraint::Min(0),    // List
-            Constraint::Length(1), // Footer
+            Constraint::Length(1),                        // Title bar
+            Constraint::Length(2),                        // Directory info (top border + text)
+            Constraint::Length(u16::from(show_root_bar)), // Share of the root total
+            Constraint::Min(0),                           // List
+            Constraint::Length(1),                        // Footer
         ])
         .split(f.area());
 
-    let [title_area, dir_info_area, list_area, footer_area] = *chunks else {
+    let [
+        title_area,
+        dir_info_area,
+        root_bar_area,
+        list_area,
+        footer_area,
+    ] = *chunks
+    else {
         return;
     };
 
-    render_title_bar(f, title_area);
+    render_title_bar(f, title_area, app.disk_space, app.args.ascii);
     render_directory_info(f, app, dir_info_area);
+    if show_root_bar {
+        render_root_bar(f, app, root_bar_area);
+    }
     render_file_list(f, app, list_area);
     render_footer(f, app, footer_area);
 
-    if app.show_help {
-        render_help_overlay(f);
+    match app.overlay {
+        Some(Overlay::Help) => render_help_overlay(f, app),
+        Some(Overlay::RecentFiles) => render_recent_files_overlay(f, app),
+        Some(Overlay::Histogram) => render_histogram_overlay(f, app),
+        Some(Overlay::Details) => render_details_overlay(f, app),
+        Some(Overlay::Stats) => render_stats_overlay(f, app),
+        Some(Overlay::Peek) => render_peek_overlay(f, app),
+        None => {}
     }
+
+    if !app.theme.colors {
+        strip_colors(f.buffer_mut());
+    }
+}
+
+/// Reset every cell to the terminal's own colors, keeping bold, reverse and other modifiers
+///
+/// Drawing in color and stripping it afterwards keeps `--color never` out of every render function.
+pub fn strip_colors(buffer: &mut Buffer) {
+    for cell in &mut buffer.content {
+        cell.set_fg(Color::Reset).set_bg(Color::Reset);
+    }
+}
+
+/// Draw the screen shown while the initial scan runs: a spinner and a live item counter
+pub fn render_scanning(f: &mut Frame, path: &Path, items: usize, elapsed: Duration, ascii: bool) {
+    const SPINNER: [&str; 10] = ["⠋", "⠙", "⠹", "⠸", "⠼", "⠴", "⠦", "⠧", "⠇", "⠏"];
+    const SPINNER_ASCII: [&str; 4] = ["|", "/", "-", "\\"];
+
+    let area = f.area();
+    if area.width < MIN_WIDTH || area.height < MIN_HEIGHT {
+        render_too_small(f);
+        return;
+    }
+    if let Some(title_area) = Layout::default()
+        .constraints([Constraint::Length(1), Constraint::Min(0)])
+        .split(area)
+        .first()
+    {
+        render_title_bar(f, *title_area, None, ascii);
+    }
+
+    let frames: &[&str] = if ascii { &SPINNER_ASCII } else { &SPINNER };
+    let frame_index = (elapsed.as_millis() / 100) as usize % frames.len();
+    let spinner = frames.get(frame_index).copied().unwrap_or_default();
+    let width = area.width.saturating_sub(4).min(60);
+    let path_text = truncate_with_ellipsis(
+        &path.display().to_string(),
+        usize::from(width).saturating_sub(16),
+    );
+    let lines = vec![
+        Line::from(""),
+        Line::from(vec![
+            Span::styled(spinner, Style::default().fg(COLOR_HELP_HEADER)),
+            Span::raw(format!(" Scanning {}", path_text)),
+        ]),
+        Line::from(Span::styled(
+            format!("{} items found ({:.1}s)", items, elapsed.as_secs_f64()),
+            Style::default().fg(COLOR_DIR_INFO),
+        )),
+        Line::from(""),
+        Line::from(Span::styled(
+            "Press q to cancel",
+            Style::default().fg(COLOR_HELP_HINT),
+        )),
+    ];
+
+    let popup = centered_rect(width, lines.len() as u16 + 2, area);
+    let block = Paragraph::new(lines)
+        .alignment(Alignment::Center)
+        .block(Block::default().borders(Borders::ALL));
+    f.render_widget(block, popup);
 }
 
-fn render_title_bar(f: &mut Frame, area: ratatui::layout::Rect) {
+fn render_title_bar(f: &mut Frame, area: Rect, disk: Option<DiskSpace>, ascii: bool) {
     let version = env!("CARGO_PKG_VERSION");
     let terminal_width = f.area().width as usize;
 
-    // Calculate padding: " rdu vX.X.X (press ? for help)" = 1 + 3 + 2 + version.len + 8 + 1 + 10 + 1
-    let title_len = 1 + 3 + 2 + version.len() + 8 + 1 + 10 + 1; // " rdu vX.X.X (press ? for help)"
-    let padding = terminal_width.saturating_sub(title_len);
-
-    let title_bar = Paragraph::new(Line::from(vec![
+    let mut spans = vec![
         Span::raw(" "),
         Span::styled(
             "rdu",
@@ -60,104 +163,631 @@ fn render_title_bar(f: &mut Frame, area: ratatui::layout::Rect) {
                 .add_modifier(Modifier::BOLD),
         ),
         Span::raw(" for help)"),
-        Span::raw(" ".repeat(padding)),
-    ]))
-    .style(Style::default().fg(COLOR_HEADER_FG).bg(COLOR_HEADER_BG));
+    ];
+
+    // Whatever the title leaves on the right holds the disk gauge, padded to the full width
+    let title_len: usize = spans.iter().map(Span::width).sum();
+    let room = terminal_width.saturating_sub(title_len);
+    let gauge = disk.and_then(|space| disk_gauge(space, room, ascii));
+    let gauge_len = gauge.as_ref().map_or(0, |(text, _)| text.chars().count());
+    spans.push(Span::raw(" ".repeat(room.saturating_sub(gauge_len))));
+    if let Some((text, used_percent)) = gauge {
+        let style = if used_percent >= DISK_ALERT_PERCENT {
+            Style::default()
+                .fg(COLOR_SIZE_ALERT)
+                .add_modifier(Modifier::BOLD)
+        } else {
+            Style::default()
+        };
+        spans.push(Span::styled(text, style));
+    }
+
+    let title_bar = Paragraph::new(Line::from(spans))
+        .style(Style::default().fg(COLOR_HEADER_FG).bg(COLOR_HEADER_BG));
     f.render_widget(title_bar, area);
 }
 
-fn render_directory_info(f: &mut Frame, app: &App, area: ratatui::layout::Rect) {
+/// Share of the volume in use from which the title bar gauge is drawn in the alert color
+const DISK_ALERT_PERCENT: f64 = 90.0;
+
+/// The title bar's disk gauge and the percentage it shows, in the longest form that fits in `room`
+///
+/// The free space goes first and then the bar as the terminal narrows, leaving just the
+/// percentage; `None` if not even that fits beside the title.
+fn disk_gauge(space: DiskSpace, room: usize, ascii: bool) -> Option<(String, f64)> {
+    const GAUGE_BAR_WIDTH: usize = 10;
+    let percent = space.used_percent();
+    let bar = if ascii {
+        render_bar_ascii(percent, GAUGE_BAR_WIDTH)
+    } else {
+        render_bar(percent, GAUGE_BAR_WIDTH)
+    };
+    let bar = format!("Disk [{:<width$}]", bar, width = GAUGE_BAR_WIDTH);
+    [
+        format!(
+            "{} {:.0}% used, {} free ",
+            bar,
+            percent,
+            format_size(space.available)
+        ),
+        format!("{} {:.0}% used ", bar, percent),
+        format!("Disk {:.0}% used ", percent),
+    ]
+    .into_iter()
+    // Keep at least a space between the help text and the gauge
+    .find(|text| text.chars().count() < room)
+    .map(|text| (text, percent))
+}
+
+fn render_directory_info(f: &mut Frame, app: &App, area: Rect) {
     let children = app.current_children();
     let item_count = children.len();
-    let current_size = format_size(app.current_total_size());
     let current_path = app.current_path();
 
-    let dir_info = format!(
-        " {} ({} visible, {})",
-        current_path.display(),
-        item_count,
-        current_size
-    );
-    let dir_line = Paragraph::new(Line::from(vec![Span::styled(
-        dir_info,
-        Style::default().fg(COLOR_DIR_INFO),
-    )]))
-    .block(Block::default().borders(Borders::TOP | Borders::LEFT | Borders::RIGHT));
+    let dir_info = if app.filter_inverted() {
+        // What the filters would keep is hidden; the directory-only view keeps directories
+        let (listed, hidden) = if app.dirs_only {
+            ("files", "directories")
+        } else {
+            ("entries", "matches")
+        };
+        format!(
+            " {} ({} {}, {} of {}, {} hidden)",
+            current_path.display(),
+            item_count,
+            listed,
+            format_metric(
+                app,
+                children
+                    .iter()
+                    .map(|c| c.borrow().metric(app.inode_view))
+                    .sum()
+            ),
+            format_metric(app, app.current_total_metric()),
+            hidden
+        )
+    } else if app.dirs_only {
+        format!(
+            " {} ({} directories, {} of {}, files hidden)",
+            current_path.display(),
+            item_count,
+            format_metric(app, app.list_total_metric()),
+            format_metric(app, app.current_total_metric())
+        )
+    } else {
+        format!(
+            " {} ({} visible, {})",
+            current_path.display(),
+            item_count,
+            format_metric(app, app.current_total_metric())
+        )
+    };
+    let mut spans = vec![Span::styled(dir_info, Style::default().fg(COLOR_DIR_INFO))];
+    if let Some(scan) = &app.live_scan {
+        spans.push(Span::styled(
+            format!("  scanning, {} items so far", scan.items()),
+            Style::default().fg(COLOR_SIZE_WARN),
+        ));
+    }
+    if let Some(delta) = app.snapshot_delta() {
+        let color = if delta > 0 {
+            COLOR_GROWTH
+        } else {
+            COLOR_SHRINK
+        };
+        spans.push(Span::styled(
+            format!("  {} since snapshot", format_size_delta(delta)),
+            Style::default().fg(color),
+        ));
+    }
+    if !app.excluded.is_empty() {
+        spans.push(Span::styled(
+            format!("  {} excluded", app.excluded.len()),
+            Style::default().fg(COLOR_HELP_HINT),
+        ));
+    }
+    if app.current_node.borrow().partial {
+        spans.push(Span::styled(
+            "  estimate: not every directory was read",
+            Style::default().fg(COLOR_SIZE_WARN),
+        ));
+    }
+    if let Some(target) = app.args.target.filter(|&t| t > 0) {
+        const TARGET_BAR_WIDTH: usize = 10;
+        let percent = app.current_total_size() as f64 / target as f64 * 100.0;
+        spans.push(Span::raw(format!(
+            "  [{:<width$}] {:.0}% of target",
+            bar(app, percent.min(100.0), TARGET_BAR_WIDTH),
+            percent,
+            width = TARGET_BAR_WIDTH
+        )));
+    }
+    let dir_line = Paragraph::new(Line::from(spans))
+        .block(Block::default().borders(Borders::TOP | Borders::LEFT | Borders::RIGHT));
     f.render_widget(dir_line, area);
 }
 
-fn render_file_list(f: &mut Frame, app: &mut App, area: ratatui::layout::Rect) {
+/// Draw the current directory's share of the whole scan, so it stays in view while deep in the tree
+fn render_root_bar(f: &mut Frame, app: &App, area: Rect) {
+    const ROOT_BAR_WIDTH: usize = 20;
+    let root_total = app.root_total_metric();
+    let percent = if root_total > 0 {
+        app.current_total_metric() as f64 / root_total as f64 * 100.0
+    } else {
+        0.0
+    };
+    let line = Line::from(vec![
+        Span::raw(" Root: "),
+        Span::styled(
+            format!(
+                "[{:<width$}]",
+                bar(app, percent, ROOT_BAR_WIDTH),
+                width = ROOT_BAR_WIDTH
+            ),
+            Style::default().fg(COLOR_PERCENT),
+        ),
+        Span::styled(
+            format!(" {:.1}% of {}", percent, format_metric(app, root_total)),
+            Style::default().fg(COLOR_DIR_INFO),
+        ),
+    ]);
+    let root_line =
+        Paragraph::new(line).block(Block::default().borders(Borders::LEFT | Borders::RIGHT));
+    f.render_widget(root_line, area);
+}
+
+fn render_file_list(f: &mut Frame, app: &mut App, area: Rect) {
     let children = app.current_children();
-    let parent_size = app.current_total_size();
+    let inodes = app.inode_view;
+    let parent_size = app.list_total_metric();
+    let percent_inodes = app.percent_inodes();
+    let percent_total = app.percent_total();
+    let hidden = app.hidden_children();
+    let loose_files = app.loose_files();
+
+    app.header_columns.clear();
+    if children.is_empty() && loose_files.is_none() {
+        render_empty_message(f, area);
+        return;
+    }
+
+    let bar_max = if app.bar_relative_to_max {
+        children
+            .iter()
+            .map(|c| c.borrow().metric(inodes))
+            .max()
+            .unwrap_or(0)
+    } else if let Some(total) = app.disk_basis_total().filter(|_| !inodes) {
+        total
+    } else {
+        parent_size
+    };
+
+    // Fixed-width columns, their separators and the mark indicator; the name gets the rest
+    let block = Block::default().borders(Borders::LEFT | Borders::RIGHT | Borders::BOTTOM);
+    let inner = block.inner(area);
+    let columns = app.display_columns();
+    let precision = app.percent_precision;
+    let fixed_width: usize = columns
+        .iter()
+        .filter_map(|c| c.width(precision))
+        .sum::<usize>()
+        + COLUMN_SEPARATOR.len() * columns.len().saturating_sub(1)
+        + 1;
+    let name_space = (inner.width as usize).saturating_sub(fixed_width);
 
-    let items: Vec<ListItem> = children
+    let mut items: Vec<ListItem> = children
         .iter()
         .map(|node_rc| {
             let node = node_rc.borrow();
-            let size_str = format_size(node.size);
-            let name = &node.name;
-            let percent = if parent_size > 0 {
-                (node.size as f64 / parent_size as f64) * 100.0
+            let value = node.metric(inodes);
+            let percent = if percent_total > 0 {
+                (node.metric(percent_inodes) as f64 / percent_total as f64) * 100.0
             } else {
                 0.0
             };
-
-            // Create bar graph using fractional block characters
-            let bar = render_bar(percent, 10);
-
-            // Prefix: / for directories, space for files
-            let prefix = if node.is_dir { "/" } else { " " };
-            let name_color = if node.is_dir {
-                COLOR_DIRECTORY
+            let bar_percent = if bar_max > 0 {
+                (value as f64 / bar_max as f64) * 100.0
             } else {
-                COLOR_FILE
+                0.0
             };
 
-            // Multi-colored line: olive size | white percent | bar | colored name
-            ListItem::new(Line::from(vec![
-                Span::styled(format!("{:>10}", size_str), Style::default().fg(COLOR_SIZE)),
-                Span::raw(" | "),
-                Span::styled(
-                    format!("{:>5.1}%", percent),
-                    Style::default().fg(COLOR_PERCENT),
-                ),
-                Span::raw(" | "),
-                Span::styled(format!("{:10}", bar), Style::default().fg(COLOR_PERCENT)),
-                Span::raw(" | "),
+            let marked = app.is_marked(&node.path);
+            let marker = if marked { "*" } else { " " };
+
+            // Show how the entry changed since the last refresh or the --compare snapshot
+            let delta_span = app.size_delta(&node.path, node.size).map(|delta| {
+                let color = if delta > 0 {
+                    COLOR_GROWTH
+                } else {
+                    COLOR_SHRINK
+                };
                 Span::styled(
-                    format!("{}{}", prefix, name),
-                    Style::default().fg(name_color),
-                ),
-            ]))
+                    format!("  ({})", format_size_delta(delta)),
+                    Style::default().fg(color),
+                )
+            });
+            // Subtrees on another filesystem, directories an estimate scan didn't finish and
+            // directories with entries that couldn't be read (their size is only a lower bound)
+            let mut tag_spans: Vec<Span> = [
+                node.is_mount_point
+                    .then(|| Span::styled(" (mount)", Style::default().fg(COLOR_MOUNT_POINT))),
+                node.partial
+                    .then(|| Span::styled(" (partial)", Style::default().fg(COLOR_SIZE_WARN))),
+                (node.is_dir && node.error_count > 0).then(|| {
+                    Span::styled(
+                        format!(" ({} unreadable)", node.error_count),
+                        Style::default().fg(COLOR_SIZE_ALERT),
+                    )
+                }),
+            ]
+            .into_iter()
+            .flatten()
+            .collect();
+            let tags_width: usize = tag_spans.iter().map(Span::width).sum();
+            let delta_width = delta_span.as_ref().map_or(0, |d| d.width());
+            let name_width = name_space.saturating_sub(delta_width);
+            let name_is_last = columns.last() == Some(&ColumnKind::Name);
+
+            let mut spans = vec![Span::styled(marker, Style::default().fg(COLOR_MARKED))];
+            for (i, column) in columns.iter().enumerate() {
+                if i > 0 {
+                    spans.push(Span::raw(COLUMN_SEPARATOR));
+                }
+                let cell = match column {
+                    ColumnKind::Size if inodes => Span::styled(
+                        format!("{:>10}", node.item_count),
+                        Style::default().fg(size_color(app, node.size)),
+                    ),
+                    ColumnKind::Size => Span::styled(
+                        format_size_aligned(node.size),
+                        Style::default().fg(size_color(app, node.size)),
+                    ),
+                    ColumnKind::Disk => disk_size_span(&node),
+                    ColumnKind::Percent => Span::styled(
+                        format!(
+                            "{:>width$.precision$}%",
+                            percent,
+                            width = ColumnKind::Percent
+                                .width(precision)
+                                .map_or(0, |w| w.saturating_sub(1))
+                        ),
+                        Style::default().fg(COLOR_PERCENT),
+                    ),
+                    // Bar graph using fractional block characters
+                    ColumnKind::Bar => Span::styled(
+                        format!("{:10}", bar(app, bar_percent, 10)),
+                        Style::default().fg(COLOR_PERCENT),
+                    ),
+                    ColumnKind::Count => {
+                        let count = if node.is_dir {
+                            node.child_count().to_string()
+                        } else {
+                            String::new()
+                        };
+                        Span::styled(format!("{:>8}", count), Style::default().fg(COLOR_PERCENT))
+                    }
+                    ColumnKind::Name => {
+                        let prefix = name_prefix(app, node.is_dir);
+                        let name_color = if marked {
+                            COLOR_MARKED
+                        } else if node.is_mount_point {
+                            COLOR_MOUNT_POINT
+                        } else if node.is_dir {
+                            COLOR_DIRECTORY
+                        } else {
+                            COLOR_FILE
+                        };
+                        // Truncate the name so the row never overflows the list; tags stay whole
+                        let name = truncate_with_ellipsis(
+                            &format!("{}{}", prefix, app.display_name(node_rc)),
+                            name_width.saturating_sub(tags_width),
+                        );
+                        // Pad so the following columns stay aligned
+                        let padding = if name_is_last {
+                            0
+                        } else {
+                            name_width.saturating_sub(name.width() + tags_width)
+                        };
+                        spans.push(Span::styled(name, Style::default().fg(name_color)));
+                        spans.extend(std::mem::take(&mut tag_spans));
+                        Span::raw(" ".repeat(padding))
+                    }
+                };
+                spans.push(cell);
+            }
+            spans.extend(delta_span);
+
+            ListItem::new(Line::from(spans))
         })
         .collect();
 
-    let list = List::new(items)
-        .block(Block::default().borders(Borders::LEFT | Borders::RIGHT | Borders::BOTTOM))
-        .highlight_style(
-            Style::default()
-                .bg(COLOR_HIGHLIGHT_BG)
-                .fg(COLOR_HIGHLIGHT_FG),
-        );
+    // Entries cut off by --max-items and files left out of the directory-only view
+    // are summarized in rows that can't be selected
+    let mut summary_rows = Vec::new();
+    if let Some((count, total)) = hidden {
+        summary_rows.push(format!(
+            " … and {} more ({})",
+            count,
+            format_metric(app, total)
+        ));
+    }
+    if let Some((count, total)) = loose_files {
+        summary_rows.push(format!(
+            " (files) {} files, {}",
+            count,
+            format_metric(app, total)
+        ));
+    }
+    for row in &summary_rows {
+        items.push(ListItem::new(Line::from(Span::styled(
+            row.as_str(),
+            Style::default().fg(COLOR_HELP_HINT),
+        ))));
+    }
+
+    // Column labels above the rows, unless that would leave no room for the rows
+    let header_height = u16::from(inner.height > 1);
+    let header_area = Rect {
+        height: header_height,
+        ..inner
+    };
+    let rows_area = Rect {
+        y: inner.y + header_height,
+        height: inner.height - header_height,
+        ..inner
+    };
+    f.render_widget(block, area);
+    if header_height > 0 {
+        render_list_header(f, app, header_area, &columns, name_space);
+    }
+
+    // Keep lines free at the bottom so the summary rows show when scrolled to the end
+    app.list_height = (rows_area.height as usize).saturating_sub(summary_rows.len());
+    // Keep the selection in view when the list height changed, e.g. after a resize
+    app.scroll_to_selected();
+
+    let list = List::new(items).highlight_style(app.theme.highlight_style());
+
+    f.render_stateful_widget(list, rows_area, &mut app.state);
+}
+
+/// Draw the column labels, marking the column the list is sorted by
+///
+/// Records where each label is on `app` so a click on it can sort by that column.
+fn render_list_header(
+    f: &mut Frame,
+    app: &mut App,
+    area: Rect,
+    columns: &[ColumnKind],
+    name_space: usize,
+) {
+    let arrow = match (app.args.ascii, app.sort_ascending) {
+        (false, true) => "▲",
+        (false, false) => "▼",
+        (true, true) => "^",
+        (true, false) => "v",
+    };
+    // Only the first column sorting by the active mode gets the arrow
+    let sorted_column = columns.iter().position(|c| c.sort_mode() == app.sort_mode);
 
-    f.render_stateful_widget(list, area, &mut app.state);
+    let style = Style::default()
+        .fg(COLOR_HELP_HEADER)
+        .add_modifier(Modifier::BOLD);
+    // Leave room for the mark indicator
+    let mut x = area.x + 1;
+    let mut spans = vec![Span::raw(" ")];
+    for (i, column) in columns.iter().enumerate() {
+        if i > 0 {
+            spans.push(Span::raw(" ".repeat(COLUMN_SEPARATOR.len())));
+            x = x.saturating_add(COLUMN_SEPARATOR.len() as u16);
+        }
+        let label = if sorted_column == Some(i) {
+            format!("{} {}", column.label(), arrow)
+        } else {
+            column.label().to_string()
+        };
+        let width = column.width(app.percent_precision).unwrap_or(name_space);
+        // Numbers are right-aligned, so their labels are too
+        let text = match column {
+            ColumnKind::Bar | ColumnKind::Name => {
+                format!(" {:<w$}", label, w = width.saturating_sub(1))
+            }
+            _ => format!("{:>width$}", label),
+        };
+        let text = truncate_with_ellipsis(&text, width);
+        let text_width = u16::try_from(text.width()).unwrap_or(u16::MAX);
+        app.header_columns.push((
+            Rect {
+                x,
+                width: text_width.min(area.right().saturating_sub(x)),
+                ..area
+            },
+            *column,
+        ));
+        x = x.saturating_add(text_width);
+        spans.push(Span::styled(text, style));
+    }
+    f.render_widget(Paragraph::new(Line::from(spans)), area);
 }
 
-fn render_footer(f: &mut Frame, app: &App, area: ratatui::layout::Rect) {
+/// Allocated size of an entry when it's more than 10% off its apparent size
+///
+/// Entries taking less space than their length (compressed or sparse files) stand out.
+fn disk_size_span(node: &FileNode) -> Span<'static> {
+    let (apparent, disk) = (node.apparent_size, node.disk_size);
+    if disk.abs_diff(apparent).saturating_mul(10) <= apparent {
+        return Span::raw(" ".repeat(10));
+    }
+    let color = if disk < apparent {
+        COLOR_COMPRESSED
+    } else {
+        COLOR_PERCENT
+    };
+    Span::styled(format_size_aligned(disk), Style::default().fg(color))
+}
+
+/// Color of the size column: red above `--alert-size`, yellow above `--warn-size`
+fn size_color(app: &App, size: u64) -> Color {
+    let above = |threshold: u64| threshold > 0 && size > threshold;
+    if above(app.args.alert_size) {
+        COLOR_SIZE_ALERT
+    } else if above(app.args.warn_size) {
+        COLOR_SIZE_WARN
+    } else {
+        COLOR_SIZE
+    }
+}
+
+/// Render a bar with block characters, or ASCII ones with `--ascii`
+fn bar(app: &App, percent: f64, width: usize) -> String {
+    if app.args.ascii {
+        render_bar_ascii(percent, width)
+    } else {
+        render_bar(percent, width)
+    }
+}
+
+/// Prefix of a listed name: / for directories, space for files (D and F in ASCII mode)
+fn name_prefix(app: &App, is_dir: bool) -> &'static str {
+    match (app.args.ascii, is_dir) {
+        (false, true) => "/",
+        (false, false) => " ",
+        (true, true) => "D ",
+        (true, false) => "F ",
+    }
+}
+
+/// Format a total in the active metric: item count in inode view, human size otherwise
+fn format_metric(app: &App, value: u64) -> String {
+    if app.inode_view {
+        format!("{} items", value)
+    } else {
+        format_size(value)
+    }
+}
+
+fn render_empty_message(f: &mut Frame, area: Rect) {
+    let block = Block::default().borders(Borders::LEFT | Borders::RIGHT | Borders::BOTTOM);
+    let inner = block.inner(area);
+    f.render_widget(block, area);
+
+    // Vertically center the message inside the list area
+    let message_area = Rect {
+        y: inner.y + inner.height.saturating_sub(1) / 2,
+        height: inner.height.min(1),
+        ..inner
+    };
+    let message = Paragraph::new(Span::styled(
+        "This directory is empty",
+        Style::default().fg(COLOR_HELP_HINT),
+    ))
+    .alignment(Alignment::Center);
+    f.render_widget(message, message_area);
+}
+
+fn render_footer(f: &mut Frame, app: &App, area: Rect) {
     let terminal_width = f.area().width as usize;
-    let status_msg = app.status_message.as_deref().unwrap_or("");
+    let delete_prompt = app
+        .selected_node()
+        .filter(|_| app.confirm_delete)
+        .map(|node| {
+            let node = node.borrow();
+            let action = if app.args.dry_run {
+                "Dry run: log deletion of"
+            } else if app.args.no_trash {
+                "Permanently delete"
+            } else {
+                "Move to trash"
+            };
+            format!(
+                "{} {} ({})? [y/N]",
+                action,
+                node.name,
+                format_size(node.size)
+            )
+        });
+    let text_prompt = app.prompt.as_ref().map(|prompt| match prompt.kind {
+        PromptKind::Rename => format!("Rename to: {}_", prompt.input),
+        PromptKind::ExportDu => match std::env::current_dir() {
+            Ok(base) => format!(
+                "Export du listing to (relative to {}, empty for clipboard): {}_",
+                base.display(),
+                prompt.input
+            ),
+            Err(_) => format!(
+                "Export du listing to (empty for clipboard): {}_",
+                prompt.input
+            ),
+        },
+        PromptKind::Move => format!(
+            "Move {} item(s) to: {}_",
+            app.move_sources().len(),
+            prompt.input
+        ),
+    });
+    let status_msg = text_prompt
+        .as_deref()
+        .or(delete_prompt.as_deref())
+        .or(app.status_message.as_deref())
+        .unwrap_or("");
     let sort_order = if app.sort_ascending {
         "ascending"
     } else {
         "descending"
     };
-    let current_size = format_size(app.current_total_size());
-    let footer_left = format!(
-        "Sort mode: {} {}  Total disk usage: {}",
+    let total_label = if app.inode_view {
+        "Total items"
+    } else {
+        "Total disk usage"
+    };
+    let current_size = format_metric(app, app.current_total_metric());
+    let mut footer_left = format!(
+        "Sort mode: {} {}  {}: {}",
         app.sort_mode.name(),
         sort_order,
+        total_label,
         current_size
     );
+    if let Some(target) = app.args.target.filter(|&t| t > 0) {
+        footer_left.push_str(&format!(
+            "  Freed: {} of {} ({:.0}%)",
+            format_size(app.freed_size),
+            format_size(target),
+            app.freed_size as f64 / target as f64 * 100.0
+        ));
+    }
+    if app.filter_inverted() {
+        footer_left.push_str("  Filter: inverted");
+    }
+    match app.percent_basis {
+        PercentBasis::Items if !app.inode_view => footer_left.push_str("  Percent: items"),
+        PercentBasis::Disk => footer_left.push_str("  Percent: disk"),
+        _ => {}
+    }
+    if !app.marked.is_empty() {
+        footer_left.push_str(&format!(
+            "  Marked: {} ({})",
+            app.marked.len(),
+            format_size(app.marked_size)
+        ));
+    }
+    if let Some(cumulative) = app.cumulative_metric
+        && let Some(selected) = app.state.selected()
+    {
+        let total = app.list_total_metric();
+        let percent = if total > 0 {
+            cumulative as f64 / total as f64 * 100.0
+        } else {
+            0.0
+        };
+        footer_left.push_str(&format!(
+            "  Top {}: {} ({:.0}%)",
+            selected + 1,
+            format_metric(app, cumulative),
+            percent
+        ));
+    }
     let footer_right = if !status_msg.is_empty() {
         format!("  {}", status_msg)
     } else {
@@ -177,7 +807,7 @@ fn render_footer(f: &mut Frame, app: &App, area: ratatui::layout::Rect) {
     f.render_widget(footer, area);
 }
 
-fn render_help_overlay(f: &mut Frame) {
+fn render_help_overlay(f: &mut Frame, app: &mut App) {
     let help_text = vec![
         Line::from(""),
         Line::from(Span::styled(
@@ -208,7 +838,21 @@ fn render_help_overlay(f: &mut Frame) {
         )),
         Line::from("    o / l / Enter   Enter directory"),
         Line::from("    u / h / Bksp    Go up one level"),
-        Line::from("    r               Refresh current view"),
+        Line::from("    f               Go back into the directory just left"),
+        Line::from("    r               Refresh current view if anything changed"),
+        Line::from("    Ctrl+r          Rescan current view even if nothing changed"),
+        Line::from("    y               Copy path to clipboard"),
+        Line::from("    Y               Copy size to clipboard"),
+        Line::from("    E               Export listing as du output"),
+        Line::from("    d               Delete selected entry"),
+        Line::from("    U               Restore last trashed entry"),
+        Line::from("    x               Hide selected entry for this session"),
+        Line::from("    X               Show all hidden entries again"),
+        Line::from("    R               Rename selected entry"),
+        Line::from("    M               Move marked (or selected) entries"),
+        Line::from("    Space           Mark/unmark entry"),
+        Line::from("    B <letter>      Bookmark current directory"),
+        Line::from("    ' <letter>      Jump to bookmark"),
         Line::from(""),
         Line::from(Span::styled(
             "  Display:",
@@ -219,6 +863,24 @@ fn render_help_overlay(f: &mut Frame) {
         Line::from("    s               Toggle sort by size"),
         Line::from("    m               Toggle sort by mtime"),
         Line::from("    c               Toggle sort by count"),
+        Line::from("    Tab             Cycle sort: size, mtime, count, name"),
+        Line::from("    g               Group dirs first/last/mixed"),
+        Line::from("    =               Reset to biggest first, all entries"),
+        Line::from("    v               Scale bars to largest entry"),
+        Line::from("    %               Percent of size, item count or disk"),
+        Line::from("    t               Recently modified files"),
+        Line::from("    z               File size histogram"),
+        Line::from("    i               Details of selected entry"),
+        Line::from("    e               Peek into selected directory"),
+        Line::from("    S               Statistics of the whole tree"),
+        Line::from("    C               Collapse single-child directory chains"),
+        Line::from("    #               Toggle item count (inode) view"),
+        Line::from("    D               Toggle directory-only view"),
+        Line::from("    !               Invert the display filters"),
+        Line::from("    +               Show total of rows up to selection"),
+        Line::from("    p               Toggle the share-of-root bar"),
+        Line::from("    b               Toggle the bar column"),
+        Line::from("    L               Count symlinks as link, target or zero"),
         Line::from(""),
         Line::from(Span::styled(
             "  Other:",
@@ -226,34 +888,312 @@ fn render_help_overlay(f: &mut Frame) {
                 .fg(COLOR_HELP_HEADER)
                 .add_modifier(Modifier::BOLD),
         )),
+        Line::from("    V               Verify tree sizes"),
         Line::from("    ?               Toggle this help"),
         Line::from("    q / Esc         Quit"),
         Line::from(""),
         Line::from(Span::styled(
-            "  Press any key to close",
+            "  About:",
+            Style::default()
+                .fg(COLOR_HELP_HEADER)
+                .add_modifier(Modifier::BOLD),
+        )),
+        Line::from(format!("    Version         {}", env!("CARGO_PKG_VERSION"))),
+        Line::from(format!(
+            "    Target          {}",
+            option_env!("RDU_TARGET").unwrap_or("unknown")
+        )),
+        Line::from(format!(
+            "    Platform        {}/{} ({})",
+            std::env::consts::OS,
+            std::env::consts::ARCH,
+            if cfg!(windows) {
+                "drive letters"
+            } else {
+                "unix devices"
+            }
+        )),
+        Line::from(""),
+        Line::from(Span::styled(
+            "  j/k or ↑/↓ to scroll, ?/Esc/q to close",
             Style::default().fg(COLOR_HELP_HINT),
         )),
         Line::from(""),
     ];
 
-    let help_height = help_text.len() as u16 + 2;
-    let help_width = 42;
-    let area = f.area();
-    let help_area = ratatui::layout::Rect {
-        x: area.width.saturating_sub(help_width) / 2,
-        y: area.height.saturating_sub(help_height) / 2,
-        width: help_width.min(area.width),
-        height: help_height.min(area.height),
+    render_overlay(f, app, " Help ", help_text, 50);
+}
+
+fn render_recent_files_overlay(f: &mut Frame, app: &mut App) {
+    let mut lines = vec![Line::from("")];
+    if app.recent_files.is_empty() {
+        lines.push(Line::from("  No files below this directory"));
+    }
+    let time_format = app.args.time_format;
+    for file in &app.recent_files {
+        let modified = file
+            .modified_time
+            .map(|t| format_time(t, time_format))
+            .unwrap_or_else(|| "unknown".to_string());
+        lines.push(Line::from(vec![
+            Span::styled(
+                format!("  {:>width$}", modified, width = time_format.width()),
+                Style::default().fg(COLOR_HELP_HINT),
+            ),
+            Span::styled(
+                format!("  {:>10}  ", format_size(file.size)),
+                Style::default().fg(COLOR_SIZE),
+            ),
+            Span::raw(file.relative_path.display().to_string()),
+        ]));
+    }
+    lines.push(Line::from(""));
+
+    let width = f.area().width.saturating_sub(4).min(100);
+    render_overlay(f, app, " Recently Modified Files ", lines, width);
+}
+
+fn render_histogram_overlay(f: &mut Frame, app: &mut App) {
+    const BAR_WIDTH: usize = 20;
+
+    let max_count = app.histogram.iter().map(|b| b.count).max().unwrap_or(0);
+    let mut lines = vec![Line::from("")];
+    for bucket in &app.histogram {
+        let percent = if max_count > 0 {
+            (bucket.count as f64 / max_count as f64) * 100.0
+        } else {
+            0.0
+        };
+        lines.push(Line::from(vec![
+            Span::styled(
+                format!("  {:>8}  ", bucket.label),
+                Style::default().fg(COLOR_HELP_HEADER),
+            ),
+            Span::raw(format!(
+                "{:<width$}",
+                bar(app, percent, BAR_WIDTH),
+                width = BAR_WIDTH
+            )),
+            Span::raw(format!("  {:>8} files", bucket.count)),
+            Span::styled(
+                format!("  {:>10}", format_size(bucket.total_size)),
+                Style::default().fg(COLOR_SIZE),
+            ),
+        ]));
+    }
+    lines.push(Line::from(""));
+
+    render_overlay(f, app, " File Size Histogram ", lines, 62);
+}
+
+fn render_details_overlay(f: &mut Frame, app: &mut App) {
+    let Some(details) = &app.details else {
+        return;
+    };
+    let kind = match (details.is_dir, details.is_symlink) {
+        (_, true) => "symlink",
+        (true, false) => "directory",
+        (false, false) => "file",
     };
+    let modified = details.modified_time.map_or_else(
+        || "unknown".to_string(),
+        // Absolute formats would only repeat the timestamp
+        |t| match app.args.time_format {
+            TimeFormat::Relative => format!("{} ({})", format_timestamp(t), format_age(t)),
+            TimeFormat::Iso | TimeFormat::Short => format_timestamp(t),
+        },
+    );
+
+    let mut lines = vec![
+        Line::from(""),
+        overlay_field("Path", details.path.display().to_string()),
+        overlay_field("Type", kind.to_string()),
+        overlay_field(
+            "Size",
+            format!(
+                "{} ({} bytes)",
+                format_size(details.size),
+                format_bytes_grouped(details.size)
+            ),
+        ),
+        overlay_field("Modified", modified),
+    ];
+    if details.is_dir {
+        lines.push(overlay_field("Children", details.child_count.to_string()));
+        lines.push(overlay_field(
+            "Total items",
+            details.descendant_count.to_string(),
+        ));
+    }
+    lines.push(Line::from(""));
 
-    f.render_widget(Clear, help_area);
-    let help_block = Paragraph::new(help_text)
+    let width = f.area().width.saturating_sub(4).min(100);
+    render_overlay(f, app, " Details ", lines, width);
+}
+
+fn render_stats_overlay(f: &mut Frame, app: &mut App) {
+    // Top-level entries listed with their scan time under --scan-timing
+    const SLOWEST_ROWS: usize = 10;
+
+    let Some(stats) = &app.tree_stats else {
+        return;
+    };
+    let largest_file = stats.largest_file.as_ref().map_or_else(
+        || "none".to_string(),
+        |(path, size)| format!("{} ({})", path.display(), format_size(*size)),
+    );
+
+    let mut lines = vec![
+        Line::from(""),
+        overlay_field(
+            "Total size",
+            format!(
+                "{} ({} bytes)",
+                format_size(stats.total_size),
+                format_bytes_grouped(stats.total_size)
+            ),
+        ),
+        overlay_field("Files", stats.files.to_string()),
+        overlay_field("Directories", stats.dirs.to_string()),
+        overlay_field(
+            "Deepest path",
+            format!(
+                "{} ({} levels)",
+                stats.deepest_path.display(),
+                stats.max_depth
+            ),
+        ),
+        overlay_field("Largest file", largest_file),
+        overlay_field("Average file", format_size(stats.average_file_size())),
+        overlay_field("Errors", stats.error_count.to_string()),
+        overlay_field("No permission", stats.denied_count.to_string()),
+        Line::from(""),
+    ];
+    if !stats.scan_timings.is_empty() {
+        lines.push(Line::from(Span::styled(
+            "  Slowest to scan:",
+            Style::default().fg(COLOR_HELP_HEADER),
+        )));
+        for (path, time) in stats.scan_timings.iter().take(SLOWEST_ROWS) {
+            lines.push(Line::from(format!(
+                "  {:>9.2}s  {}",
+                time.as_secs_f64(),
+                path.display()
+            )));
+        }
+        lines.push(Line::from(""));
+    }
+
+    let width = f.area().width.saturating_sub(4).min(100);
+    render_overlay(f, app, " Statistics ", lines, width);
+}
+
+fn render_peek_overlay(f: &mut Frame, app: &mut App) {
+    const BAR_WIDTH: usize = 20;
+
+    let Some(node) = app.peek.clone() else {
+        return;
+    };
+    let node = node.borrow();
+    let total = node.metric(app.inode_view);
+    let mut lines = vec![Line::from("")];
+    for child in &node.children {
+        let child = child.borrow();
+        let value = child.metric(app.inode_view);
+        let percent = if total > 0 {
+            value as f64 / total as f64 * 100.0
+        } else {
+            0.0
+        };
+        let size = if app.inode_view {
+            format!("{:>10}", value)
+        } else {
+            format_size_aligned(value)
+        };
+        let name_color = if child.is_dir {
+            COLOR_DIRECTORY
+        } else {
+            COLOR_FILE
+        };
+        lines.push(Line::from(vec![
+            Span::styled(format!("  {}  ", size), Style::default().fg(COLOR_SIZE)),
+            Span::styled(
+                format!(
+                    "{:<width$}  {:>5.1}%  ",
+                    bar(app, percent, BAR_WIDTH),
+                    percent,
+                    width = BAR_WIDTH
+                ),
+                Style::default().fg(COLOR_PERCENT),
+            ),
+            Span::styled(
+                format!("{}{}", name_prefix(app, child.is_dir), child.name),
+                Style::default().fg(name_color),
+            ),
+        ]));
+    }
+    if node.children.is_empty() {
+        lines.push(Line::from("  (empty directory)"));
+    }
+    lines.push(Line::from(""));
+    let title = format!(" Peek: {} ", node.name);
+    drop(node);
+
+    let width = f.area().width.saturating_sub(4).min(100);
+    render_overlay(f, app, &title, lines, width);
+}
+
+/// A `label  value` line of the details and statistics overlays
+fn overlay_field(label: &str, value: String) -> Line<'static> {
+    Line::from(vec![
+        Span::styled(
+            format!("  {:<14}", label),
+            Style::default().fg(COLOR_HELP_HEADER),
+        ),
+        Span::raw(value),
+    ])
+}
+
+/// Draw a bordered popup centered on the screen, scrolled by `app.overlay_scroll`
+///
+/// Records the popup's area and scroll range on `app` for mouse handling.
+fn render_overlay(f: &mut Frame, app: &mut App, title: &str, lines: Vec<Line>, width: u16) {
+    let height = lines.len() as u16 + 2;
+    let area = centered_rect(width, height, f.area());
+
+    let max_scroll = height.saturating_sub(area.height);
+    app.overlay_area = area;
+    app.overlay_max_scroll = max_scroll;
+    app.overlay_scroll = app.overlay_scroll.min(max_scroll);
+
+    f.render_widget(Clear, area);
+    let block = Paragraph::new(lines)
+        .scroll((app.overlay_scroll, 0))
         .block(
             Block::default()
                 .borders(Borders::ALL)
-                .title(" Help ")
+                .title(title)
                 .style(Style::default().bg(Color::Black)),
         )
         .style(Style::default().fg(Color::White).bg(Color::Black));
-    f.render_widget(help_block, help_area);
+    f.render_widget(block, area);
+}
+
+fn render_too_small(f: &mut Frame) {
+    let message = Paragraph::new("Terminal too small")
+        .alignment(Alignment::Center)
+        .wrap(Wrap { trim: true });
+    f.render_widget(message, f.area());
+}
+
+/// A rect of at most `width` x `height` centered in `area`, never extending past it
+fn centered_rect(width: u16, height: u16, area: Rect) -> Rect {
+    let width = width.min(area.width);
+    let height = height.min(area.height);
+    Rect {
+        x: area.x + (area.width - width) / 2,
+        y: area.y + (area.height - height) / 2,
+        width,
+        height,
+    }
 }
@@ -1,8 +1,15 @@
+use clap::ValueEnum;
 use number_prefix::NumberPrefix;
-use std::path::Path;
-
-#[cfg(not(windows))]
-use std::fs;
+use std::{
+    cmp::Ordering,
+    fs,
+    iter::Peekable,
+    path::{Component, Path, PathBuf},
+    str::Chars,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+use unicode_segmentation::UnicodeSegmentation;
+use unicode_width::UnicodeWidthStr;
 
 pub fn format_size(size: u64) -> String {
     match NumberPrefix::binary(size as f64) {
@@ -11,7 +18,469 @@ pub fn format_size(size: u64) -> String {
     }
 }
 
+/// Format a size in decimal units, like `1.5 kB` or `12.4 GB`
+///
+/// ```
+/// use rdu::utils::format_size_si;
+///
+/// assert_eq!(format_size_si(999), "999 B");
+/// assert_eq!(format_size_si(1_500), "1.5 kB");
+/// assert_eq!(format_size_si(12_400_000_000), "12.4 GB");
+/// ```
+pub fn format_size_si(size: u64) -> String {
+    match NumberPrefix::decimal(size as f64) {
+        NumberPrefix::Standalone(bytes) => format!("{} B", bytes),
+        NumberPrefix::Prefixed(prefix, n) => format!("{:.1} {}B", n, prefix),
+    }
+}
+
+/// Format a size the way `du -h` does, like `512`, `1.5K` or `12M`
+///
+/// Values round up, with one decimal below 10 and none from there on.
+///
+/// ```
+/// use rdu::utils::format_size_du;
+///
+/// assert_eq!(format_size_du(0), "0");
+/// assert_eq!(format_size_du(512), "512");
+/// assert_eq!(format_size_du(1_024), "1.0K");
+/// assert_eq!(format_size_du(1_536), "1.5K");
+/// assert_eq!(format_size_du(10 * 1_024 + 1), "11K");
+/// assert_eq!(format_size_du(3 << 30), "3.0G");
+/// ```
+pub fn format_size_du(size: u64) -> String {
+    match NumberPrefix::binary(size as f64) {
+        NumberPrefix::Standalone(bytes) => format!("{}", bytes),
+        NumberPrefix::Prefixed(prefix, n) => {
+            let unit = prefix.symbol().trim_end_matches('i');
+            if n < 10.0 {
+                format!("{:.1}{}", (n * 10.0).ceil() / 10.0, unit)
+            } else {
+                format!("{:.0}{}", n.ceil(), unit)
+            }
+        }
+    }
+}
+
+/// Format a signed size change like `+1.2 MiB` or `-300.0 KiB`
+pub fn format_size_delta(delta: i128) -> String {
+    let sign = if delta < 0 { '-' } else { '+' };
+    let magnitude = u64::try_from(delta.unsigned_abs()).unwrap_or(u64::MAX);
+    format!("{}{}", sign, format_size(magnitude))
+}
+
+/// Format an exact byte count with a comma between each group of three digits
+///
+/// ```
+/// use rdu::utils::format_bytes_grouped;
+///
+/// assert_eq!(format_bytes_grouped(0), "0");
+/// assert_eq!(format_bytes_grouped(999), "999");
+/// assert_eq!(format_bytes_grouped(1_000), "1,000");
+/// assert_eq!(format_bytes_grouped(1_234_567_890), "1,234,567,890");
+/// assert_eq!(format_bytes_grouped(u64::MAX), "18,446,744,073,709,551,615");
+/// ```
+pub fn format_bytes_grouped(bytes: u64) -> String {
+    let digits = bytes.to_string();
+    let mut grouped = String::with_capacity(digits.len() + digits.len() / 3);
+    for (i, digit) in digits.chars().enumerate() {
+        if i > 0 && (digits.len() - i).is_multiple_of(3) {
+            grouped.push(',');
+        }
+        grouped.push(digit);
+    }
+    grouped
+}
+
+/// Format a size into a fixed 10-character column with decimal points and units aligned
+///
+/// ```
+/// use rdu::utils::format_size_aligned;
+///
+/// assert_eq!(format_size_aligned(512), " 512   B  ");
+/// assert_eq!(format_size_aligned(1_536), "   1.5 KiB");
+/// assert_eq!(format_size_aligned(300 << 20), " 300.0 MiB");
+/// assert_eq!(format_size_aligned(12 << 30), "  12.0 GiB");
+///
+/// for size in [7, 512, 1_536, 300 << 20, 12 << 30, 1 << 40] {
+///     let formatted = format_size_aligned(size);
+///     assert_eq!(formatted.len(), 10);
+///     let (number, unit) = formatted.split_at(7);
+///     assert!(unit.starts_with(|c: char| c.is_ascii_alphabetic()));
+///     // Whole bytes end in the column before the others' decimal point
+///     match number.find('.') {
+///         Some(dot) => assert_eq!(dot, 4),
+///         None => assert!(number[..4].ends_with(|c: char| c.is_ascii_digit())),
+///     }
+/// }
+/// ```
+pub fn format_size_aligned(size: u64) -> String {
+    match NumberPrefix::binary(size as f64) {
+        // Leave room for the missing ".d" so integers line up with prefixed values
+        NumberPrefix::Standalone(bytes) => format!("{:>4}   {:<3}", bytes, "B"),
+        NumberPrefix::Prefixed(prefix, n) => format!("{:>6.1} {:<3}", n, format!("{}B", prefix)),
+    }
+}
+
+/// Format how long ago a timestamp was, like `42s ago` or `3d ago`
+pub fn format_age(time: SystemTime) -> String {
+    let Ok(elapsed) = SystemTime::now().duration_since(time) else {
+        return "in future".to_string();
+    };
+    let secs = elapsed.as_secs();
+    match secs {
+        0..60 => format!("{}s ago", secs),
+        60..3_600 => format!("{}m ago", secs / 60),
+        3_600..86_400 => format!("{}h ago", secs / 3_600),
+        86_400..31_536_000 => format!("{}d ago", secs / 86_400),
+        _ => format!("{}y ago", secs / 31_536_000),
+    }
+}
+
+/// How modification times are shown (`--time-format`)
+#[derive(ValueEnum, Debug, Clone, Copy, PartialEq, Default)]
+pub enum TimeFormat {
+    /// How long ago, like `3d ago`
+    #[default]
+    Relative,
+    /// Date and time, like `2024-01-15 14:30`
+    Iso,
+    /// Month and day, with the time for the last six months and the year before that, like `ls -l`
+    Short,
+}
+
+impl TimeFormat {
+    /// Widest string [`format_time`] gives in this format, for aligning columns
+    pub fn width(self) -> usize {
+        match self {
+            TimeFormat::Relative => 9,
+            TimeFormat::Iso => 16,
+            TimeFormat::Short => 12,
+        }
+    }
+}
+
+/// Format a timestamp in `format`; absolute times are in UTC
+///
+/// ```
+/// use rdu::utils::{TimeFormat, format_time};
+/// use std::time::{Duration, UNIX_EPOCH};
+///
+/// let time = UNIX_EPOCH + Duration::from_secs(1_705_329_000);
+/// assert_eq!(format_time(time, TimeFormat::Iso), "2024-01-15 14:30");
+/// assert_eq!(format_time(time, TimeFormat::Short), "Jan 15  2024");
+/// ```
+pub fn format_time(time: SystemTime, format: TimeFormat) -> String {
+    const MONTHS: [&str; 12] = [
+        "Jan", "Feb", "Mar", "Apr", "May", "Jun", "Jul", "Aug", "Sep", "Oct", "Nov", "Dec",
+    ];
+    // Same cutoff as `ls -l` for showing the year instead of the time
+    const SIX_MONTHS: u64 = 15_778_476;
+
+    if format == TimeFormat::Relative {
+        return format_age(time);
+    }
+    let Some(((year, month, day), time_of_day)) = civil_time(time) else {
+        return "before 1970".to_string();
+    };
+    let (hour, minute) = (time_of_day / 3_600, time_of_day % 3_600 / 60);
+    match format {
+        TimeFormat::Short => {
+            let month = MONTHS
+                .get(month.saturating_sub(1) as usize)
+                .copied()
+                .unwrap_or("???");
+            let recent = SystemTime::now()
+                .duration_since(time)
+                .is_ok_and(|age| age.as_secs() < SIX_MONTHS);
+            if recent {
+                format!("{} {:>2} {:02}:{:02}", month, day, hour, minute)
+            } else {
+                format!("{} {:>2}  {}", month, day, year)
+            }
+        }
+        _ => format!(
+            "{:04}-{:02}-{:02} {:02}:{:02}",
+            year, month, day, hour, minute
+        ),
+    }
+}
+
+/// Format a timestamp as `YYYY-MM-DD HH:MM:SS UTC`
+pub fn format_timestamp(time: SystemTime) -> String {
+    let Some(((year, month, day), time_of_day)) = civil_time(time) else {
+        return "before 1970".to_string();
+    };
+    format!(
+        "{:04}-{:02}-{:02} {:02}:{:02}:{:02} UTC",
+        year,
+        month,
+        day,
+        time_of_day / 3_600,
+        time_of_day % 3_600 / 60,
+        time_of_day % 60
+    )
+}
+
+/// UTC date (year, month, day) of a timestamp and the seconds since midnight, `None` before 1970
+fn civil_time(time: SystemTime) -> Option<((u64, u64, u64), u64)> {
+    let secs = time.duration_since(UNIX_EPOCH).ok()?.as_secs();
+    let (days, time_of_day) = (secs / 86_400, secs % 86_400);
+
+    // Civil date from days since the epoch (Howard Hinnant's algorithm)
+    let z = days + 719_468;
+    let era = z / 146_097;
+    let day_of_era = z % 146_097;
+    let year_of_era =
+        (day_of_era - day_of_era / 1_460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let mp = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = year_of_era + era * 400 + u64::from(month <= 2);
+    Some(((year, month, day), time_of_day))
+}
+
+/// Parse a human-readable size like `512`, `10K`, `1.5G` or `2MiB` into bytes (binary units)
+pub fn parse_size(input: &str) -> Result<u64, String> {
+    let input = input.trim();
+    let split = input
+        .find(|c: char| !c.is_ascii_digit() && c != '.')
+        .unwrap_or(input.len());
+    let (number, unit) = input.split_at(split);
+    let value: f64 = number
+        .parse()
+        .map_err(|_| format!("invalid size '{}'", input))?;
+
+    let multiplier: u64 = match unit.trim().to_ascii_uppercase().as_str() {
+        "" | "B" => 1,
+        "K" | "KB" | "KIB" => 1 << 10,
+        "M" | "MB" | "MIB" => 1 << 20,
+        "G" | "GB" | "GIB" => 1 << 30,
+        "T" | "TB" | "TIB" => 1 << 40,
+        "P" | "PB" | "PIB" => 1 << 50,
+        _ => return Err(format!("invalid size unit '{}'", unit.trim())),
+    };
+    Ok((value * multiplier as f64) as u64)
+}
+
+/// Parse a point in time given as a UTC date like `2024-01-01` or an age like `30d`
+///
+/// Ages count back from now in `s`econds, `m`inutes, `h`ours, `d`ays, `w`eeks or
+/// `y`ears of 365 days.
+///
+/// ```
+/// use rdu::utils::parse_time;
+/// use std::time::{Duration, SystemTime, UNIX_EPOCH};
+///
+/// assert_eq!(parse_time("2024-01-15"), Ok(UNIX_EPOCH + Duration::from_secs(1_705_276_800)));
+/// assert_eq!(parse_time("1970-01-01"), Ok(UNIX_EPOCH));
+/// let month_ago = parse_time("30d").unwrap();
+/// let age = SystemTime::now().duration_since(month_ago).unwrap();
+/// assert!(age >= Duration::from_secs(30 * 86_400) && age < Duration::from_secs(30 * 86_400 + 60));
+/// assert!(parse_time("2024-02-30").is_err());
+/// assert!(parse_time("2024-01-32").is_err());
+/// assert!(parse_time("2024-01-9999999999999999").is_err());
+/// assert!(parse_time("2024-01-18446744073709551615").is_err());
+/// assert!(parse_time("30x").is_err());
+/// ```
+pub fn parse_time(input: &str) -> Result<SystemTime, String> {
+    let input = input.trim();
+    let invalid = || {
+        format!(
+            "invalid time '{}': expected a date like 2024-01-01 or an age like 30d",
+            input
+        )
+    };
+
+    if input.contains('-') {
+        let mut parts = input.splitn(3, '-').map(str::parse::<u64>);
+        let (Some(Ok(year)), Some(Ok(month)), Some(Ok(day))) =
+            (parts.next(), parts.next(), parts.next())
+        else {
+            return Err(invalid());
+        };
+        if !(1970..=9999).contains(&year) || !(1..=12).contains(&month) || !(1..=31).contains(&day)
+        {
+            return Err(invalid());
+        }
+        let time = days_from_civil(year, month, day)
+            .checked_mul(86_400)
+            .and_then(|secs| UNIX_EPOCH.checked_add(Duration::from_secs(secs)))
+            .ok_or_else(invalid)?;
+        // Days past the end of the month roll over into the next one
+        return match civil_time(time) {
+            Some(((y, m, d), _)) if (y, m, d) == (year, month, day) => Ok(time),
+            _ => Err(invalid()),
+        };
+    }
+
+    let split = input
+        .find(|c: char| !c.is_ascii_digit())
+        .unwrap_or(input.len());
+    let (number, unit) = input.split_at(split);
+    let value: u64 = number.parse().map_err(|_| invalid())?;
+    let unit_secs: u64 = match unit {
+        "s" => 1,
+        "m" => 60,
+        "h" => 3_600,
+        "d" => 86_400,
+        "w" => 7 * 86_400,
+        "y" => 365 * 86_400,
+        _ => return Err(invalid()),
+    };
+    SystemTime::now()
+        .checked_sub(Duration::from_secs(value.saturating_mul(unit_secs)))
+        .ok_or_else(invalid)
+}
+
+/// Days since the Unix epoch of a date on or after 1970-01-01 (Howard Hinnant's algorithm)
+fn days_from_civil(year: u64, month: u64, day: u64) -> u64 {
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year / 400;
+    let year_of_era = year % 400;
+    let mp = (month + 9) % 12;
+    let day_of_year = (153 * mp + 2) / 5 + day - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+    (era * 146_097 + day_of_era).saturating_sub(719_468)
+}
+
+/// Compare two names so runs of digits order by their numeric value
+///
+/// Everything else compares character by character, so the order is case-sensitive
+/// like a plain string comparison. Numbers of any length are supported.
+///
+/// ```
+/// use rdu::utils::natural_cmp;
+/// use std::cmp::Ordering;
+///
+/// assert_eq!(natural_cmp("file2", "file10"), Ordering::Less);
+/// assert_eq!(natural_cmp("file10", "file10"), Ordering::Equal);
+/// assert_eq!(natural_cmp("x9y", "x10a"), Ordering::Less);
+/// assert_eq!(natural_cmp("v1.10.0", "v1.9.3"), Ordering::Greater);
+/// assert_eq!(natural_cmp("report", "report2"), Ordering::Less);
+/// assert_eq!(natural_cmp("99999999999999999999", "100000000000000000000"), Ordering::Less);
+///
+/// let mut names = ["img12.png", "img10.png", "img2.png", "Img3.png", "img1.png"];
+/// names.sort_by(|a, b| natural_cmp(a, b));
+/// assert_eq!(names, ["Img3.png", "img1.png", "img2.png", "img10.png", "img12.png"]);
+///
+/// // Leading zeros only break ties, so the order stays total
+/// assert_eq!(natural_cmp("a01", "a1"), Ordering::Less);
+/// assert_eq!(natural_cmp("a01", "a2"), Ordering::Less);
+/// ```
+pub fn natural_cmp(a: &str, b: &str) -> Ordering {
+    fn digit_run(chars: &mut Peekable<Chars>) -> String {
+        let mut run = String::new();
+        while let Some(c) = chars.next_if(char::is_ascii_digit) {
+            run.push(c);
+        }
+        run
+    }
+
+    let (mut a_chars, mut b_chars) = (a.chars().peekable(), b.chars().peekable());
+    loop {
+        match (a_chars.peek().copied(), b_chars.peek().copied()) {
+            (None, None) => return a.cmp(b),
+            (None, Some(_)) => return Ordering::Less,
+            (Some(_), None) => return Ordering::Greater,
+            (Some(x), Some(y)) if x.is_ascii_digit() && y.is_ascii_digit() => {
+                let a_run = digit_run(&mut a_chars);
+                let b_run = digit_run(&mut b_chars);
+                let (a_num, b_num) = (a_run.trim_start_matches('0'), b_run.trim_start_matches('0'));
+                // Without leading zeros, a longer number is a larger one
+                let order = a_num.len().cmp(&b_num.len()).then_with(|| a_num.cmp(b_num));
+                if order != Ordering::Equal {
+                    return order;
+                }
+            }
+            (Some(x), Some(y)) => {
+                if x != y {
+                    return x.cmp(&y);
+                }
+                a_chars.next();
+                b_chars.next();
+            }
+        }
+    }
+}
+
+/// Move `path` from below `old_prefix` to below `new_prefix`, or `None` if it isn't below it
+pub fn rebase_path(path: &Path, old_prefix: &Path, new_prefix: &Path) -> Option<PathBuf> {
+    let rest = path.strip_prefix(old_prefix).ok()?;
+    if rest.as_os_str().is_empty() {
+        Some(new_prefix.to_path_buf())
+    } else {
+        Some(new_prefix.join(rest))
+    }
+}
+
+/// Make `path` absolute against `base`, dropping `.` components and resolving `..` ones
+///
+/// `..` is resolved lexically, like a shell's `cd`, so a path through a symbolic link
+/// keeps the link rather than its target. Trailing slashes are dropped too.
+///
+/// ```
+/// use rdu::utils::resolve_path;
+/// use std::path::{Path, PathBuf};
+///
+/// let base = Path::new("/home/user/project");
+/// assert_eq!(resolve_path(Path::new("."), base), PathBuf::from("/home/user/project"));
+/// assert_eq!(resolve_path(Path::new("../docs/"), base), PathBuf::from("/home/user/docs"));
+/// assert_eq!(resolve_path(Path::new("/tmp/./x/.."), base), PathBuf::from("/tmp"));
+/// assert_eq!(resolve_path(Path::new("../../../.."), base), PathBuf::from("/"));
+/// ```
+pub fn resolve_path(path: &Path, base: &Path) -> PathBuf {
+    let mut resolved = PathBuf::new();
+    for component in base.join(path).components() {
+        match component {
+            Component::CurDir => {}
+            // Going above the root stays at the root
+            Component::ParentDir => {
+                resolved.pop();
+            }
+            component => resolved.push(component),
+        }
+    }
+    resolved
+}
+
+/// Shorten text to at most `max_width` terminal columns, ending with `…` when cut
+///
+/// Cuts happen on grapheme boundaries so multibyte names are never split mid-character.
+pub fn truncate_with_ellipsis(text: &str, max_width: usize) -> String {
+    if text.width() <= max_width {
+        return text.to_string();
+    }
+    if max_width == 0 {
+        return String::new();
+    }
+
+    let mut result = String::new();
+    let mut width = 0;
+    for grapheme in text.graphemes(true) {
+        let grapheme_width = grapheme.width();
+        if width + grapheme_width > max_width - 1 {
+            break;
+        }
+        result.push_str(grapheme);
+        width += grapheme_width;
+    }
+    result.push('…');
+    result
+}
+
 /// Render a progress bar using Unicode block characters (1/8 to 8/8 precision)
+///
+/// The bar is never wider than `width` cells:
+///
+/// ```
+/// use rdu::utils::render_bar;
+///
+/// assert_eq!(render_bar(100.0, 10).chars().count(), 10);
+/// assert_eq!(render_bar(55.0, 10), "█████▌");
+/// assert_eq!(render_bar(0.0, 10), "");
+/// ```
 pub fn render_bar(percent: f64, width: usize) -> String {
     const PARTIAL_CHARS: [char; 7] = ['▏', '▎', '▍', '▌', '▋', '▊', '▉'];
 
@@ -29,6 +498,66 @@ pub fn render_bar(percent: f64, width: usize) -> String {
     bar
 }
 
+/// Render a progress bar for terminals without block characters
+///
+/// Full cells are `#`; a trailing partial cell is `=` when at least half full and `-` otherwise.
+///
+/// ```
+/// use rdu::utils::render_bar_ascii;
+///
+/// assert_eq!(render_bar_ascii(100.0, 10), "##########");
+/// assert_eq!(render_bar_ascii(55.0, 10), "#####=");
+/// assert_eq!(render_bar_ascii(52.0, 10), "#####-");
+/// assert_eq!(render_bar_ascii(30.0, 10), "###");
+/// ```
+pub fn render_bar_ascii(percent: f64, width: usize) -> String {
+    let fraction = percent / 100.0 * width as f64;
+    let full_blocks = fraction.floor() as usize;
+    // Round to eighths like the Unicode bar so float noise doesn't add a partial cell
+    let partial = ((fraction - full_blocks as f64) * 8.0).round() as usize;
+
+    let mut bar = "#".repeat(full_blocks.min(width));
+    if full_blocks < width {
+        match partial {
+            0 | 8.. => {}
+            1..4 => bar.push('-'),
+            _ => bar.push('='),
+        }
+    }
+    bar
+}
+
+/// Size an entry contributes to totals
+///
+/// By default only files count, with their apparent length. In disk-usage mode every
+/// entry (directories included) counts the space allocated for it, like `du`.
+pub fn entry_size(meta: &fs::Metadata, disk_usage: bool) -> u64 {
+    #[cfg(not(windows))]
+    if disk_usage {
+        use std::os::unix::fs::MetadataExt;
+        return meta.blocks() * 512;
+    }
+
+    #[cfg(windows)]
+    let _ = disk_usage;
+
+    if meta.is_file() { meta.len() } else { 0 }
+}
+
+/// Space allocated on disk for an entry, directories included
+///
+/// Windows doesn't report allocation through std, so files count their length there.
+pub fn allocated_size(meta: &fs::Metadata) -> u64 {
+    #[cfg(not(windows))]
+    {
+        use std::os::unix::fs::MetadataExt;
+        meta.blocks() * 512
+    }
+
+    #[cfg(windows)]
+    if meta.is_file() { meta.len() } else { 0 }
+}
+
 /// Get the drive letter for a path (Windows-specific)
 #[cfg(windows)]
 pub fn get_drive_letter(path: &Path) -> Option<char> {
@@ -42,8 +571,66 @@ pub fn get_drive_letter(path: &Path) -> Option<char> {
 
 #[cfg(not(windows))]
 pub fn get_volume_id(path: &Path) -> Option<u64> {
-    use std::os::unix::fs::MetadataExt;
-    fs::metadata(path).ok().map(|m| m.dev())
+    fs::metadata(path).ok().as_ref().and_then(device_id)
+}
+
+/// Id of the device (filesystem) an entry lives on
+///
+/// Windows doesn't expose volume ids through stable std, so there it's always `None`.
+pub fn device_id(meta: &fs::Metadata) -> Option<u64> {
+    #[cfg(not(windows))]
+    {
+        use std::os::unix::fs::MetadataExt;
+        Some(meta.dev())
+    }
+
+    #[cfg(windows)]
+    {
+        let _ = meta;
+        None
+    }
+}
+
+/// Capacity of a filesystem and the space still available on it, in bytes
+///
+/// `free` also counts blocks reserved for root, which `available` leaves out.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct DiskSpace {
+    pub total: u64,
+    pub free: u64,
+    pub available: u64,
+}
+
+impl DiskSpace {
+    /// Percentage of the space usable by ordinary users that is taken, as `df` reports it
+    ///
+    /// ```
+    /// use rdu::utils::DiskSpace;
+    ///
+    /// // 10 bytes reserved for root don't count as used
+    /// let space = DiskSpace { total: 100, free: 50, available: 40 };
+    /// assert_eq!(space.used_percent().round(), 56.0);
+    /// ```
+    pub fn used_percent(&self) -> f64 {
+        let used = self.total.saturating_sub(self.free);
+        let usable = used + self.available;
+        if usable == 0 {
+            0.0
+        } else {
+            used as f64 / usable as f64 * 100.0
+        }
+    }
+}
+
+/// Capacity and available space of the filesystem holding `path`, or `None` if it can't be queried
+pub fn disk_space(path: &Path) -> Option<DiskSpace> {
+    let stats = fs4::statvfs(path).ok()?;
+    Some(DiskSpace {
+        total: stats.total_space(),
+        free: stats.free_space(),
+        available: stats.available_space(),
+    })
+    .filter(|space| space.total > 0)
 }
 
 /// Get the number of CPUs for parallelism
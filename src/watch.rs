@@ -0,0 +1,60 @@
+use notify::{Event, RecommendedWatcher, RecursiveMode, Watcher};
+use std::{
+    path::{Path, PathBuf},
+    sync::mpsc::{self, Receiver},
+    time::{Duration, Instant},
+};
+
+/// How long the filesystem has to stay quiet before changes are reported
+const DEBOUNCE: Duration = Duration::from_millis(500);
+
+/// Recursive filesystem watcher for `--watch`, debouncing bursts of events
+pub struct FsWatcher {
+    // Dropping the watcher stops the notifications
+    _watcher: RecommendedWatcher,
+    events: Receiver<notify::Result<Event>>,
+    changed: Vec<PathBuf>,
+    last_event: Option<Instant>,
+}
+
+impl FsWatcher {
+    /// Start watching `root` and everything below it
+    pub fn new(root: &Path) -> notify::Result<Self> {
+        let (tx, events) = mpsc::channel();
+        let mut watcher = notify::recommended_watcher(tx)?;
+        watcher.watch(root, RecursiveMode::Recursive)?;
+        Ok(Self {
+            _watcher: watcher,
+            events,
+            changed: Vec::new(),
+            last_event: None,
+        })
+    }
+
+    /// How often the event loop should call [`FsWatcher::changed_below`]
+    pub fn tick(&self) -> Duration {
+        DEBOUNCE / 2
+    }
+
+    /// Drain queued events and report whether anything below `dir` changed
+    ///
+    /// Changes are only reported once no new event arrived for the debounce period,
+    /// so a burst like extracting an archive triggers a single refresh.
+    pub fn changed_below(&mut self, dir: &Path) -> bool {
+        for event in self.events.try_iter().flatten() {
+            self.changed.extend(event.paths);
+            self.last_event = Some(Instant::now());
+        }
+
+        if self.last_event.is_none_or(|t| t.elapsed() < DEBOUNCE) {
+            return false;
+        }
+        self.last_event = None;
+        let changed = std::mem::take(&mut self.changed);
+        // Backends differ in reporting paths as given or absolute
+        let absolute_dir = dir.canonicalize().ok();
+        changed.iter().any(|path| {
+            path.starts_with(dir) || absolute_dir.as_ref().is_some_and(|d| path.starts_with(d))
+        })
+    }
+}
@@ -0,0 +1,301 @@
+mod common;
+
+use clap::Parser;
+use common::{TestResult, args, child, tree};
+use rdu::{App, Args, file_node::verify_sizes, manifest::load_manifest, scan_dir};
+use std::fs;
+
+#[test]
+fn read_only_refuses_destructive_actions() -> TestResult {
+    let dir = tree(&["a.txt=100", "docs/b.md=2000"])?;
+    let args = args(dir.path(), &["--read-only", "--no-trash"]);
+    let mut app = App::new(scan_dir(dir.path(), &args), args);
+
+    app.select(Some(0));
+    app.request_delete();
+    assert!(!app.confirm_delete);
+    app.delete_selected();
+    app.rename_selected("renamed".to_string());
+    app.start_move();
+    assert!(app.prompt.is_none());
+    app.move_marked("docs");
+
+    assert!(dir.path().join("a.txt").exists());
+    assert!(dir.path().join("docs/b.md").exists());
+    assert!(!dir.path().join("renamed").exists());
+    assert_eq!(app.root.borrow().size, 2_100);
+    assert!(
+        app.status_message
+            .as_deref()
+            .is_some_and(|m| m.starts_with("Read-only mode"))
+    );
+    Ok(())
+}
+
+#[test]
+fn manifest_trees_refuse_destructive_actions() -> TestResult {
+    // The manifest's paths exist locally, so acting on them would touch real files
+    let dir = tree(&["a.txt=100", "docs/b.md=2000"])?;
+    let manifest = dir.path().join("manifest.tsv");
+    fs::write(
+        &manifest,
+        format!(
+            "{0}/\t0\n{0}/a.txt\t100\n{0}/docs/b.md\t2000\n",
+            dir.path().display()
+        ),
+    )?;
+    let manifest_arg = manifest.to_string_lossy().to_string();
+    let args = Args::parse_from(["rdu", "--from-manifest", &manifest_arg, "--no-trash"]);
+    let mut app = App::new(load_manifest(&manifest)?, args);
+
+    app.select(Some(0));
+    app.request_delete();
+    assert!(!app.confirm_delete);
+    app.delete_selected();
+    app.start_rename();
+    app.start_move();
+    assert!(app.prompt.is_none());
+
+    assert!(dir.path().join("a.txt").exists());
+    assert!(dir.path().join("docs/b.md").exists());
+    assert_eq!(app.root.borrow().size, 2_100);
+    assert!(
+        app.status_message
+            .as_deref()
+            .is_some_and(|m| m.contains("loaded from a manifest"))
+    );
+    Ok(())
+}
+
+#[test]
+fn refresh_leaves_a_directory_replaced_by_a_file() -> TestResult {
+    let dir = tree(&["a.txt=100", "docs/b.md=2000", "docs/nested/c.bin=30000"])?;
+    let args = args(dir.path(), &[]);
+    let mut app = App::new(scan_dir(dir.path(), &args), args);
+    assert!(app.navigate_to(&dir.path().join("docs/nested")));
+
+    fs::remove_dir_all(dir.path().join("docs"))?;
+    fs::write(dir.path().join("docs"), vec![b'x'; 500])?;
+    app.refresh();
+
+    assert!(app.path_history.is_empty());
+    assert_eq!(app.current_path(), dir.path());
+    let docs = child(&app.root, "docs")?;
+    assert!(!docs.borrow().is_dir);
+    assert!(docs.borrow().children.is_empty());
+    assert_eq!(docs.borrow().size, 500);
+    assert_eq!(app.root.borrow().size, 600);
+    assert!(
+        app.selected_node()
+            .is_some_and(|n| n.borrow().name == "docs")
+    );
+    verify_sizes(&app.root.borrow())?;
+    Ok(())
+}
+
+#[test]
+fn current_total_is_the_scanned_node_size() -> TestResult {
+    let dir = tree(&["a.txt=100", "docs/b.md=2000", "docs/nested/c.bin=30000"])?;
+    let args = args(dir.path(), &[]);
+    let mut app = App::new(scan_dir(dir.path(), &args), args);
+
+    assert_eq!(app.current_total_size(), app.current_node.borrow().size);
+    assert_eq!(app.current_total_size(), 32_100);
+    assert!(app.navigate_to(&dir.path().join("docs")));
+    assert_eq!(app.current_total_size(), app.current_node.borrow().size);
+    assert_eq!(app.current_total_size(), 32_000);
+    Ok(())
+}
+
+#[test]
+fn going_up_selects_the_directory_just_left() -> TestResult {
+    let dir = tree(&["a.txt=100", "big/c.bin=30000", "small/b.md=10"])?;
+    let args = args(dir.path(), &[]);
+    let mut app = App::new(scan_dir(dir.path(), &args), args);
+
+    // Largest first, so small/ isn't the entry selected by default
+    let index = app
+        .current_children()
+        .iter()
+        .position(|c| c.borrow().name == "small")
+        .ok_or("small/ is not listed")?;
+    assert_ne!(index, 0);
+    app.select(Some(index));
+    app.enter_dir();
+    assert_eq!(app.current_path(), dir.path().join("small"));
+
+    app.go_up();
+    assert_eq!(app.current_path(), dir.path());
+    assert!(
+        app.selected_node()
+            .is_some_and(|n| n.borrow().name == "small")
+    );
+    Ok(())
+}
+
+#[cfg(unix)]
+#[test]
+fn non_utf8_names_are_renamed_and_deleted_on_disk() -> TestResult {
+    use std::{ffi::OsStr, os::unix::ffi::OsStrExt};
+
+    let dir = tree(&["a.txt=100"])?;
+    let raw = dir.path().join(OsStr::from_bytes(b"\xff.bin"));
+    fs::write(&raw, vec![b'x'; 2_000])?;
+    let args = args(dir.path(), &["--no-trash"]);
+    let mut app = App::new(scan_dir(dir.path(), &args), args);
+
+    // Largest first, so the non-UTF-8 file is selected
+    app.select(Some(0));
+    let node = app.selected_node().ok_or("nothing selected")?;
+    assert_eq!(node.borrow().path, raw);
+
+    // Submitting the lossy display name unchanged leaves the file alone
+    let display_name = node.borrow().name.clone();
+    app.rename_selected(display_name);
+    assert!(raw.exists());
+
+    app.rename_selected("renamed.bin".to_string());
+    assert!(!raw.exists());
+    assert!(dir.path().join("renamed.bin").exists());
+
+    fs::write(&raw, vec![b'x'; 5_000])?;
+    app.refresh();
+    app.select(Some(0));
+    assert_eq!(
+        app.selected_node().map(|n| n.borrow().path.clone()),
+        Some(raw.clone())
+    );
+    app.delete_selected();
+    assert!(!raw.exists());
+    assert_eq!(app.root.borrow().size, 2_100);
+    Ok(())
+}
+
+#[test]
+fn deleting_and_restoring_keeps_error_counts_consistent() -> TestResult {
+    let dir = tree(&["a.txt=100", "docs/b.md=2000", "docs/nested/c.bin=30000"])?;
+    let args = args(dir.path(), &[]);
+    let root = scan_dir(dir.path(), &args);
+    // Pretend nested had an unreadable entry, counted on every directory above it
+    let docs = child(&root, "docs")?;
+    for node in [&root, &docs, &child(&docs, "nested")?] {
+        let mut node = node.borrow_mut();
+        node.error_count = 1;
+        node.denied_count = 1;
+    }
+    let mut app = App::new(root, args);
+    assert!(app.navigate_to(&dir.path().join("docs")));
+    app.select(Some(0));
+    assert!(
+        app.selected_node()
+            .is_some_and(|n| n.borrow().name == "nested")
+    );
+
+    app.delete_selected();
+    assert!(
+        !dir.path().join("docs/nested").exists(),
+        "{:?}",
+        app.status_message
+    );
+    for node in [&app.root, &docs] {
+        assert_eq!(node.borrow().error_count, 0);
+        assert_eq!(node.borrow().denied_count, 0);
+    }
+
+    app.undo_trash();
+    assert!(dir.path().join("docs/nested").exists());
+    for node in [&app.root, &docs] {
+        assert_eq!(node.borrow().error_count, 1);
+        assert_eq!(node.borrow().denied_count, 1);
+    }
+    assert_eq!(app.root.borrow().size, 32_100);
+    verify_sizes(&app.root.borrow())?;
+    Ok(())
+}
+
+/// Names of the listed entries, sorted
+fn listed(app: &App) -> Vec<String> {
+    let mut names: Vec<String> = app
+        .current_children()
+        .iter()
+        .map(|c| c.borrow().name.clone())
+        .collect();
+    names.sort();
+    names
+}
+
+#[test]
+fn inverting_lists_what_the_filters_leave_out() -> TestResult {
+    let dir = tree(&[
+        "big.log=5000",
+        "big.txt=4000",
+        "tiny.log=10",
+        "tiny.txt=20",
+        "docs/b.md=2000",
+    ])?;
+
+    let open = |extra: &[&str]| {
+        let args = args(dir.path(), extra);
+        App::new(scan_dir(dir.path(), &args), args)
+    };
+
+    // Show me all the tiny files
+    let mut app = open(&["--min-size", "1K"]);
+    assert_eq!(listed(&app), ["big.log", "big.txt", "docs"]);
+    app.toggle_invert_filter();
+    assert_eq!(listed(&app), ["tiny.log", "tiny.txt"]);
+
+    // Directories aren't judged by extension, so they stay listed both ways
+    let mut app = open(&["--list-ext", "log"]);
+    assert_eq!(listed(&app), ["big.log", "docs", "tiny.log"]);
+    app.toggle_invert_filter();
+    assert_eq!(listed(&app), ["big.txt", "docs", "tiny.txt"]);
+    assert_eq!(app.root.borrow().size, 11_030);
+
+    // Filters combine: inverted, an entry is listed when it fails any of them
+    let mut app = open(&["--min-size", "1K", "--list-ext", "log"]);
+    assert_eq!(listed(&app), ["big.log", "docs"]);
+    app.toggle_invert_filter();
+    assert_eq!(listed(&app), ["big.txt", "tiny.log", "tiny.txt"]);
+    Ok(())
+}
+
+#[test]
+fn unchanged_refresh_still_updates_disk_space() -> TestResult {
+    let dir = tree(&["a.txt=100"])?;
+    let args = args(dir.path(), &[]);
+    let mut app = App::new(scan_dir(dir.path(), &args), args);
+    if app.disk_space.is_none() {
+        return Err("the temporary directory's filesystem reports no space".into());
+    }
+
+    app.disk_space = None;
+    app.refresh();
+    assert_eq!(app.status_message.as_deref(), Some("No changes detected"));
+    assert!(app.disk_space.is_some());
+    Ok(())
+}
+
+#[test]
+fn du_export_never_overwrites_a_file() -> TestResult {
+    let dir = tree(&["a.txt=100", "docs/b.md=2000"])?;
+    let args = args(dir.path(), &[]);
+    let mut app = App::new(scan_dir(dir.path(), &args), args);
+    let out_dir = tempfile::tempdir()?;
+    let out = out_dir.path().join("listing.txt");
+    let destination = out.to_string_lossy();
+
+    app.export_du(&destination);
+    let listing = fs::read_to_string(&out)?;
+    assert_eq!(listing.lines().count(), 2);
+
+    fs::write(&out, "keep me")?;
+    app.export_du(&destination);
+    assert_eq!(fs::read_to_string(&out)?, "keep me");
+    assert!(
+        app.status_message
+            .as_deref()
+            .is_some_and(|m| m.contains("already exists"))
+    );
+    Ok(())
+}
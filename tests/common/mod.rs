@@ -0,0 +1,68 @@
+//! Fixtures shared by the integration tests
+
+#![allow(dead_code)]
+
+use clap::Parser;
+use rdu::{Args, FileNode};
+use std::{cell::RefCell, error::Error, fs, path::Path, rc::Rc};
+use tempfile::TempDir;
+
+pub type TestResult = Result<(), Box<dyn Error>>;
+
+/// Build a directory tree in a fresh temporary directory
+///
+/// Each spec is a path relative to the root: a trailing `/` makes a directory, and
+/// `path=N` makes a file of N bytes. Parent directories are created as needed.
+///
+/// ```text
+/// tree(&["a.txt=100", "docs/b.md=2000", "empty/"])
+/// ```
+pub fn tree(specs: &[&str]) -> Result<TempDir, Box<dyn Error>> {
+    let dir = TempDir::new()?;
+    for spec in specs {
+        match spec.split_once('=') {
+            Some((path, size)) => {
+                let path = dir.path().join(path);
+                if let Some(parent) = path.parent() {
+                    fs::create_dir_all(parent)?;
+                }
+                fs::write(path, vec![b'x'; size.parse()?])?;
+            }
+            None => fs::create_dir_all(dir.path().join(spec))?,
+        }
+    }
+    Ok(dir)
+}
+
+/// Arguments as parsed from `rdu <extra...> <path>`
+pub fn args(path: &Path, extra: &[&str]) -> Args {
+    let path = path.to_string_lossy().to_string();
+    Args::parse_from(
+        ["rdu"]
+            .into_iter()
+            .chain(extra.iter().copied())
+            .chain([path.as_str()]),
+    )
+}
+
+/// Child of `node` named `name`
+pub fn child(
+    node: &Rc<RefCell<FileNode>>,
+    name: &str,
+) -> Result<Rc<RefCell<FileNode>>, Box<dyn Error>> {
+    node.borrow()
+        .children
+        .iter()
+        .find(|c| c.borrow().name == name)
+        .cloned()
+        .ok_or_else(|| format!("no child named {}", name).into())
+}
+
+/// Names of the children of `node`, in the order they are stored
+pub fn child_names(node: &Rc<RefCell<FileNode>>) -> Vec<String> {
+    node.borrow()
+        .children
+        .iter()
+        .map(|c| c.borrow().name.clone())
+        .collect()
+}
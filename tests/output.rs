@@ -0,0 +1,119 @@
+mod common;
+
+use common::{TestResult, args, tree};
+use rdu::{export::write_jsonl, scan_dir, scan_dir_streaming, tree_output::write_tree};
+use std::{
+    fs,
+    io::Write,
+    process::{Command, Stdio},
+    thread,
+    time::Duration,
+};
+
+const FIXTURE: &[&str] = &[
+    "a.txt=100",
+    "docs/b.md=2000",
+    "docs/nested/c.bin=30000",
+    "docs/nested/d.bin=0",
+    "empty/",
+];
+
+#[test]
+fn tree_output_matches_golden() -> TestResult {
+    let dir = tree(FIXTURE)?;
+    let root = scan_dir(dir.path(), &args(dir.path(), &[]));
+    let mut out = Vec::new();
+    write_tree(&root.borrow(), &mut out, None, 0, false)?;
+
+    let expected = format!(
+        "\
+[  31.3 KiB]  {}
+├── [  31.2 KiB]  docs
+│   ├── [  29.3 KiB]  nested
+│   │   ├── [  29.3 KiB]  c.bin
+│   │   └── [       0 B]  d.bin
+│   └── [   2.0 KiB]  b.md
+├── [     100 B]  a.txt
+└── [       0 B]  empty
+",
+        dir.path().display()
+    );
+    assert_eq!(String::from_utf8(out)?, expected);
+    Ok(())
+}
+
+#[test]
+fn tree_output_honors_max_depth_and_min_size() -> TestResult {
+    let dir = tree(FIXTURE)?;
+    let root = scan_dir(dir.path(), &args(dir.path(), &[]));
+    let mut out = Vec::new();
+    write_tree(&root.borrow(), &mut out, Some(2), 1_000, false)?;
+
+    // a.txt and empty fall under the minimum, nested's files under the depth limit
+    let expected = format!(
+        "\
+[  31.3 KiB]  {}
+└── [  31.2 KiB]  docs
+    ├── [  29.3 KiB]  nested
+    └── [   2.0 KiB]  b.md
+",
+        dir.path().display()
+    );
+    assert_eq!(String::from_utf8(out)?, expected);
+    Ok(())
+}
+
+#[test]
+fn jsonl_export_writes_a_line_per_entry() -> TestResult {
+    let dir = tree(FIXTURE)?;
+    let args = args(dir.path(), &[]);
+    let mut out = Vec::new();
+    let written = write_jsonl(scan_dir_streaming(dir.path(), &args), &mut out)?;
+
+    let entries = scan_dir_streaming(dir.path(), &args).count();
+    let lines: Vec<serde_json::Value> = String::from_utf8(out)?
+        .lines()
+        .map(serde_json::from_str)
+        .collect::<Result<_, _>>()?;
+    assert_eq!(lines.len(), entries);
+    assert_eq!(written, entries as u64);
+    // Everything below the root
+    assert_eq!(entries, 7);
+    assert_eq!(
+        entries as u64,
+        scan_dir(dir.path(), &args).borrow().item_count - 1
+    );
+    Ok(())
+}
+
+#[test]
+#[cfg(target_os = "linux")]
+#[ignore = "drives the TUI through util-linux `script` for a terminal; run with --ignored"]
+fn print_selection_keeps_clipboard_copies_off_stdout() -> TestResult {
+    let dir = tree(&["a.txt=100"])?;
+    let out_dir = tempfile::tempdir()?;
+    let out = out_dir.path().join("out");
+    // The pty `script` opens has no size until one is set
+    let command = format!(
+        "stty cols 80 rows 24; '{}' --print-selection '{}' > '{}'",
+        env!("CARGO_BIN_EXE_rdu"),
+        dir.path().display(),
+        out.display()
+    );
+    let mut script = Command::new("script")
+        .args(["-qfc", &command, "/dev/null"])
+        .stdin(Stdio::piped())
+        .stdout(Stdio::null())
+        .spawn()?;
+    let mut keys = script.stdin.take().ok_or("script has no stdin")?;
+    // Copy the selected path with y, then quit
+    for key in [b"y", b"q"] {
+        thread::sleep(Duration::from_millis(500));
+        keys.write_all(key)?;
+    }
+    assert!(script.wait()?.success());
+
+    let expected = format!("{}\n", dir.path().join("a.txt").display());
+    assert_eq!(fs::read_to_string(out)?, expected);
+    Ok(())
+}
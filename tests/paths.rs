@@ -0,0 +1,49 @@
+mod common;
+
+use common::{TestResult, args, tree};
+use rdu::{scan_dir, utils::resolve_path};
+use std::path::Path;
+
+/// Scan `input` as given from the working directory `cwd`, returning the root's name and size
+fn scan_from(cwd: &Path, input: &str) -> Result<(String, u64), Box<dyn std::error::Error>> {
+    let path = resolve_path(Path::new(input), cwd);
+    let root = scan_dir(&path, &args(&path, &[]));
+    let root = root.borrow();
+    if !root.path.is_absolute() {
+        return Err(format!("{:?} wasn't made absolute", root.path).into());
+    }
+    Ok((root.name.clone(), root.size))
+}
+
+#[test]
+fn current_dir_is_named_after_the_directory() -> TestResult {
+    let dir = tree(&["project/a.txt=100"])?;
+    let cwd = dir.path().join("project");
+    assert_eq!(scan_from(&cwd, ".")?, ("project".to_string(), 100));
+    assert_eq!(scan_from(&cwd, "./")?, ("project".to_string(), 100));
+    Ok(())
+}
+
+#[test]
+fn parent_components_are_resolved() -> TestResult {
+    let dir = tree(&["project/a.txt=100", "sibling/b.txt=200"])?;
+    let cwd = dir.path().join("project");
+    assert_eq!(scan_from(&cwd, "../sibling")?, ("sibling".to_string(), 200));
+    assert_eq!(
+        scan_from(&cwd, "../sibling/")?,
+        ("sibling".to_string(), 200)
+    );
+    Ok(())
+}
+
+#[test]
+fn absolute_paths_ignore_the_working_directory() -> TestResult {
+    let dir = tree(&["project/a.txt=100", "sibling/b.txt=200"])?;
+    let sibling = dir.path().join("sibling");
+    let input = sibling.to_string_lossy();
+    assert_eq!(
+        scan_from(&dir.path().join("project"), &input)?,
+        ("sibling".to_string(), 200)
+    );
+    Ok(())
+}
@@ -0,0 +1,206 @@
+mod common;
+
+use common::{TestResult, args, child, child_names, tree};
+use rdu::{
+    ScanCache,
+    file_node::{SymlinkSize, apply_symlink_size, verify_sizes},
+    scan_dir, scan_dir_cached,
+};
+use std::{fs, rc::Rc};
+
+const FIXTURE: &[&str] = &[
+    "a.txt=100",
+    "docs/b.md=2000",
+    "docs/nested/c.bin=30000",
+    "docs/nested/d.bin=0",
+    "empty/",
+];
+
+#[test]
+fn sizes_add_up_to_the_root() -> TestResult {
+    let dir = tree(FIXTURE)?;
+    let root = scan_dir(dir.path(), &args(dir.path(), &[]));
+
+    assert_eq!(root.borrow().size, 32_100);
+    assert_eq!(child(&root, "a.txt")?.borrow().size, 100);
+    let docs = child(&root, "docs")?;
+    assert_eq!(docs.borrow().size, 32_000);
+    assert_eq!(child(&docs, "nested")?.borrow().size, 30_000);
+    assert_eq!(child(&root, "empty")?.borrow().size, 0);
+    verify_sizes(&root.borrow())?;
+    Ok(())
+}
+
+#[test]
+fn structure_matches_the_disk() -> TestResult {
+    let dir = tree(FIXTURE)?;
+    let root = scan_dir(dir.path(), &args(dir.path(), &[]));
+
+    // Children come in name order
+    assert_eq!(child_names(&root), ["a.txt", "docs", "empty"]);
+    let docs = child(&root, "docs")?;
+    assert_eq!(child_names(&docs), ["b.md", "nested"]);
+    assert_eq!(child_names(&child(&docs, "nested")?), ["c.bin", "d.bin"]);
+
+    assert!(root.borrow().is_dir);
+    assert!(docs.borrow().is_dir);
+    assert!(!child(&root, "a.txt")?.borrow().is_dir);
+    assert!(child(&root, "empty")?.borrow().children.is_empty());
+    Ok(())
+}
+
+#[test]
+fn item_counts_include_every_entry() -> TestResult {
+    let dir = tree(FIXTURE)?;
+    let root = scan_dir(dir.path(), &args(dir.path(), &[]));
+
+    // The root, 3 entries below it, 2 in docs and 2 in nested
+    assert_eq!(root.borrow().item_count, 8);
+    assert_eq!(child(&root, "docs")?.borrow().item_count, 5);
+    assert_eq!(child(&root, "empty")?.borrow().item_count, 1);
+    assert_eq!(root.borrow().error_count, 0);
+    Ok(())
+}
+
+#[cfg(unix)]
+#[test]
+fn symlinks_count_as_the_link_unless_followed() -> TestResult {
+    let dir = tree(FIXTURE)?;
+    std::os::unix::fs::symlink("docs/b.md", dir.path().join("link"))?;
+
+    // The length of the path the link holds
+    let root = scan_dir(dir.path(), &args(dir.path(), &[]));
+    let link = child(&root, "link")?;
+    assert!(!link.borrow().is_dir);
+    assert_eq!(link.borrow().size, 9);
+    assert_eq!(root.borrow().size, 32_109);
+
+    let root = scan_dir(dir.path(), &args(dir.path(), &["--follow-links"]));
+    assert_eq!(child(&root, "link")?.borrow().size, 2_000);
+    assert_eq!(root.borrow().size, 34_100);
+    verify_sizes(&root.borrow())?;
+    Ok(())
+}
+
+#[test]
+fn scans_are_repeatable() -> TestResult {
+    let dir = tree(FIXTURE)?;
+    let args = args(dir.path(), &[]);
+    let first = scan_dir(dir.path(), &args);
+    let second = scan_dir(dir.path(), &args);
+
+    assert_eq!(child_names(&first), child_names(&second));
+    assert_eq!(first.borrow().size, second.borrow().size);
+    assert_eq!(first.borrow().item_count, second.borrow().item_count);
+    Ok(())
+}
+
+#[test]
+fn cached_scans_reuse_unchanged_directories() -> TestResult {
+    let dir = tree(FIXTURE)?;
+    let args = args(dir.path(), &[]);
+    let first = scan_dir(dir.path(), &args);
+
+    // Nothing changed, so the directories come back as the same nodes
+    let second = scan_dir_cached(dir.path(), &args, &ScanCache::from_tree(&first));
+    assert!(Rc::ptr_eq(
+        &child(&first, "docs")?,
+        &child(&second, "docs")?
+    ));
+    assert!(Rc::ptr_eq(
+        &child(&first, "empty")?,
+        &child(&second, "empty")?
+    ));
+    assert_eq!(second.borrow().size, 32_100);
+
+    // docs' own mtime stays the same, but it must be walked again to find the new file
+    fs::write(dir.path().join("docs/nested/e.bin"), vec![b'x'; 5])?;
+    let third = scan_dir_cached(dir.path(), &args, &ScanCache::from_tree(&second));
+    let docs = child(&third, "docs")?;
+    assert!(!Rc::ptr_eq(&child(&second, "docs")?, &docs));
+    assert_eq!(
+        child_names(&child(&docs, "nested")?),
+        ["c.bin", "d.bin", "e.bin"]
+    );
+    assert_eq!(third.borrow().size, 32_105);
+    assert!(Rc::ptr_eq(
+        &child(&second, "empty")?,
+        &child(&third, "empty")?
+    ));
+    verify_sizes(&third.borrow())?;
+    Ok(())
+}
+
+#[cfg(unix)]
+#[test]
+fn dir_links_are_followed_without_file_links() -> TestResult {
+    let dir = tree(FIXTURE)?;
+    std::os::unix::fs::symlink("docs/b.md", dir.path().join("file-link"))?;
+    std::os::unix::fs::symlink("docs/nested", dir.path().join("dir-link"))?;
+    std::os::unix::fs::symlink("nowhere", dir.path().join("broken-link"))?;
+
+    let root = scan_dir(dir.path(), &args(dir.path(), &["--follow-dir-links"]));
+    let dir_link = child(&root, "dir-link")?;
+    assert!(dir_link.borrow().is_dir);
+    assert_eq!(child_names(&dir_link), ["c.bin", "d.bin"]);
+    assert_eq!(dir_link.borrow().size, 30_000);
+    assert_eq!(child(&root, "file-link")?.borrow().size, 9);
+    // A link with no target can't be followed, so it counts as the link too
+    assert_eq!(child(&root, "broken-link")?.borrow().size, 7);
+    assert_eq!(root.borrow().error_count, 0);
+    assert_eq!(root.borrow().size, 62_116);
+    verify_sizes(&root.borrow())?;
+    Ok(())
+}
+
+#[cfg(unix)]
+#[test]
+fn symlink_size_counts_target_or_nothing() -> TestResult {
+    let dir = tree(FIXTURE)?;
+    std::os::unix::fs::symlink("docs/b.md", dir.path().join("link"))?;
+    std::os::unix::fs::symlink("docs/nested", dir.path().join("dir-link"))?;
+
+    let root = scan_dir(dir.path(), &args(dir.path(), &["--symlink-size", "target"]));
+    assert_eq!(child(&root, "link")?.borrow().size, 2_000);
+    // A directory's contents only count when the link is followed
+    assert_eq!(child(&root, "dir-link")?.borrow().size, 0);
+    assert_eq!(root.borrow().size, 34_100);
+
+    let root = scan_dir(dir.path(), &args(dir.path(), &["--symlink-size", "zero"]));
+    assert_eq!(child(&root, "link")?.borrow().size, 0);
+    assert_eq!(root.borrow().size, 32_100);
+
+    // Switching after the scan gives the same totals as scanning that way
+    apply_symlink_size(&root, SymlinkSize::Link);
+    assert_eq!(child(&root, "link")?.borrow().size, 9);
+    assert_eq!(child(&root, "dir-link")?.borrow().size, 11);
+    assert_eq!(root.borrow().size, 32_120);
+    verify_sizes(&root.borrow())?;
+    Ok(())
+}
+
+#[cfg(unix)]
+#[test]
+#[ignore = "permissions don't stop root; run with --ignored as a regular user"]
+fn read_errors_count_on_every_directory_above() -> TestResult {
+    use std::os::unix::fs::PermissionsExt;
+
+    let dir = tree(FIXTURE)?;
+    let nested = dir.path().join("docs/nested");
+    fs::set_permissions(&nested, fs::Permissions::from_mode(0o000))?;
+    let readable = fs::read_dir(&nested).is_ok();
+    let root = scan_dir(dir.path(), &args(dir.path(), &[]));
+    fs::set_permissions(&nested, fs::Permissions::from_mode(0o755))?;
+    if readable {
+        return Err("the unreadable directory could be read; is the test running as root?".into());
+    }
+
+    let docs = child(&root, "docs")?;
+    assert_eq!(child(&docs, "nested")?.borrow().error_count, 1);
+    assert_eq!(docs.borrow().error_count, 1);
+    assert_eq!(docs.borrow().denied_count, 1);
+    assert_eq!(child(&root, "empty")?.borrow().error_count, 0);
+    assert_eq!(root.borrow().error_count, 1);
+    assert_eq!(root.borrow().size, 2_100);
+    Ok(())
+}
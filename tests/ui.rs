@@ -0,0 +1,130 @@
+mod common;
+
+use common::{TestResult, args, tree};
+use ratatui::{Terminal, backend::TestBackend};
+use rdu::{App, app::Overlay, scan_dir, ui};
+use std::{error::Error, time::Duration};
+
+/// The rendered screen, one string per row
+fn rows(terminal: &Terminal<TestBackend>) -> Vec<String> {
+    let buffer = terminal.backend().buffer();
+    buffer
+        .content
+        .chunks(usize::from(buffer.area.width))
+        .map(|row| row.iter().map(|cell| cell.symbol()).collect())
+        .collect()
+}
+
+/// Draw `app` and check that its selected entry is on screen
+fn assert_selection_drawn(terminal: &mut Terminal<TestBackend>, app: &mut App) -> TestResult {
+    terminal.draw(|f| ui::ui(f, app))?;
+    let selected = app.selected_node().ok_or("nothing selected")?;
+    let name = selected.borrow().name.clone();
+    let rows = rows(terminal);
+    let row = rows
+        .iter()
+        .position(|row| row.contains(&name))
+        .ok_or_else(|| format!("{} is not on screen:\n{}", name, rows.join("\n")))?;
+    // Below the header rows and above the bottom border and footer
+    assert!(
+        (5..rows.len() - 2).contains(&row),
+        "{} drawn on row {}",
+        name,
+        row
+    );
+    Ok(())
+}
+
+/// An app over 40 files, more than fit in a short terminal
+fn long_list() -> Result<(tempfile::TempDir, App), Box<dyn Error>> {
+    let specs: Vec<String> = (0..40)
+        .map(|i| format!("file-{:02}.bin={}", i, i + 1))
+        .collect();
+    let specs: Vec<&str> = specs.iter().map(String::as_str).collect();
+    let dir = tree(&specs)?;
+    let args = args(dir.path(), &[]);
+    let app = App::new(scan_dir(dir.path(), &args), args);
+    Ok((dir, app))
+}
+
+#[test]
+fn jumps_keep_the_selection_on_screen() -> TestResult {
+    let (_dir, mut app) = long_list()?;
+    let mut terminal = Terminal::new(TestBackend::new(60, 15))?;
+    assert_selection_drawn(&mut terminal, &mut app)?;
+
+    app.go_to_last();
+    assert_selection_drawn(&mut terminal, &mut app)?;
+    app.page_up();
+    assert_selection_drawn(&mut terminal, &mut app)?;
+    app.go_to_first();
+    assert_selection_drawn(&mut terminal, &mut app)?;
+    // A page is the 8 rows the list got on screen
+    assert_eq!(app.list_height, 8);
+    app.page_down();
+    assert_eq!(app.state.selected(), Some(8));
+    assert_selection_drawn(&mut terminal, &mut app)?;
+    for _ in 0..5 {
+        app.page_down();
+        assert_selection_drawn(&mut terminal, &mut app)?;
+    }
+    Ok(())
+}
+
+#[test]
+fn tiny_terminals_draw_without_panicking() -> TestResult {
+    let dir = tree(&["a.txt=100", "docs/b.md=2000"])?;
+    let args = args(dir.path(), &[]);
+    let mut app = App::new(scan_dir(dir.path(), &args), args);
+    app.select(Some(0));
+    let overlays = [
+        None,
+        Some(Overlay::Help),
+        Some(Overlay::RecentFiles),
+        Some(Overlay::Histogram),
+        Some(Overlay::Details),
+        Some(Overlay::Stats),
+        Some(Overlay::Peek),
+    ];
+
+    for (width, height) in [(1, 1), (10, 3)] {
+        let mut terminal = Terminal::new(TestBackend::new(width, height))?;
+        for overlay in overlays {
+            app.overlay = None;
+            if let Some(overlay) = overlay {
+                app.toggle_overlay(overlay);
+            }
+            assert_eq!(app.overlay, overlay);
+            terminal.draw(|f| ui::ui(f, &mut app))?;
+        }
+        terminal.draw(|f| ui::render_scanning(f, dir.path(), 42, Duration::ZERO, false))?;
+    }
+
+    let mut terminal = Terminal::new(TestBackend::new(10, 3))?;
+    terminal.draw(|f| ui::ui(f, &mut app))?;
+    assert!(rows(&terminal).concat().contains("Terminal"));
+    Ok(())
+}
+
+#[test]
+fn resizing_between_draws_keeps_the_view_inside() -> TestResult {
+    let (_dir, mut app) = long_list()?;
+    let mut terminal = Terminal::new(TestBackend::new(100, 40))?;
+    app.go_to_last();
+    app.toggle_overlay(Overlay::Help);
+    terminal.draw(|f| ui::ui(f, &mut app))?;
+
+    terminal.backend_mut().resize(60, 12);
+    terminal.draw(|f| ui::ui(f, &mut app))?;
+    // The whole help box, borders included, fits the smaller screen
+    let rows = rows(&terminal);
+    let top = rows
+        .iter()
+        .position(|row| row.contains("┌ Help"))
+        .ok_or("help overlay not drawn")?;
+    assert!(rows.iter().skip(top + 1).any(|row| row.contains('└')));
+
+    app.overlay = None;
+    assert_selection_drawn(&mut terminal, &mut app)?;
+    Ok(())
+}